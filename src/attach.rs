@@ -0,0 +1,88 @@
+use std::{
+    fs::OpenOptions,
+    io::{ErrorKind, IsTerminal, Read, Write},
+    os::fd::AsRawFd,
+    time::Duration,
+};
+
+use anyhow::anyhow;
+
+use crate::{
+    config::WorkerConfig,
+    libc::RawMode,
+    log::{LogLine, LogReader},
+    project::WorkerProject,
+};
+
+// Ctrl-]
+const DETACH: u8 = 0x1d;
+// Ctrl-C, which the project can't get as a signal, since it doesn't run in this terminal
+const INTERRUPT: u8 = 0x03;
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Sends every key to the stdin of the project as it is typed, and prints the output it writes
+/// meanwhile. Ctrl-] or Ctrl-C detaches again, and leaves the project running
+pub fn attach(config: &WorkerConfig, name: &str) -> Result<(), anyhow::Error> {
+    let project = config
+        .get_state(name)?
+        .filter(|project| project.is_running())
+        .ok_or_else(|| anyhow!("{name} is not running"))?;
+
+    if !project.stdin.unwrap_or(false) {
+        return Err(anyhow!(
+            "{name} doesn't read from stdin. Set `stdin = true` on the project to attach to it"
+        ));
+    }
+
+    let mut fifo = OpenOptions::new()
+        .write(true)
+        .open(config.stdin_file(&project))
+        .map_err(|e| anyhow!("Couldn't open the stdin of {name}: {e}"))?;
+
+    let mut log = LogReader::new(config.log_file(&project));
+    let timestamps = project.logs_timestamps();
+    // Only the output written after attaching is printed
+    log.read_lines(|_| {})?;
+    std::thread::spawn(move || loop {
+        let _ =
+            log.read_lines(|line| println!("{}", LogLine::parse(line, timestamps).format(false)));
+        std::thread::sleep(POLL_INTERVAL);
+    });
+
+    let stdin = std::io::stdin();
+    let terminal = stdin.is_terminal();
+    let _mode = if terminal {
+        eprintln!("Attached to {name}, press Ctrl-] or Ctrl-C to detach");
+        let mode = RawMode::enable(stdin.as_raw_fd()).map_err(|_| {
+            anyhow!(
+                "Couldn't set up the terminal: {}",
+                std::io::Error::last_os_error()
+            )
+        })?;
+        Some(mode)
+    } else {
+        None
+    };
+
+    let mut buf = [0; 4096];
+    loop {
+        let n = match stdin.lock().read(&mut buf) {
+            Ok(n) => n,
+            Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e.into()),
+        };
+
+        let detach_key = |b: &u8| *b == DETACH || (terminal && *b == INTERRUPT);
+        let (input, detach) = match buf[..n].iter().position(detach_key) {
+            Some(i) => (&buf[..i], true),
+            None => (&buf[..n], n == 0),
+        };
+
+        fifo.write_all(input)
+            .map_err(|_| anyhow!("{name} is no longer running"))?;
+
+        if detach {
+            return Ok(());
+        }
+    }
+}
@@ -0,0 +1,89 @@
+use anyhow::{anyhow, Context};
+use serde::{Deserialize, Serialize};
+
+const DEFAULT_SHELL: &str = "sh";
+
+/// Command of a project. Either a command line, split into arguments with the same rules as a
+/// POSIX shell, or the arguments themselves
+#[derive(Deserialize, Serialize, Clone, Debug, Eq, PartialEq)]
+#[serde(untagged)]
+pub enum ProjectCommand {
+    Line(String),
+    Args(Vec<String>),
+}
+
+impl Default for ProjectCommand {
+    fn default() -> Self {
+        ProjectCommand::Args(vec![])
+    }
+}
+
+/// Run the command through a shell. `true` uses `$SHELL`, or the name of a shell, e.g. `"bash"`
+#[derive(Deserialize, Serialize, Clone, Debug, Eq, PartialEq)]
+#[serde(untagged)]
+pub enum Shell {
+    Enabled(bool),
+    Program(String),
+}
+
+impl ProjectCommand {
+    /// Runs the command line through the shell of the user
+    pub fn from_shell(line: String) -> Self {
+        ProjectCommand::Args(vec![user_shell(), "-c".to_string(), line])
+    }
+
+    /// The program to run, followed by its arguments
+    pub fn args(&self) -> Result<Vec<String>, anyhow::Error> {
+        match self {
+            ProjectCommand::Args(args) => Ok(args.clone()),
+            ProjectCommand::Line(line) => {
+                shell_words::split(line).with_context(|| format!("Couldn't parse command {line:?}"))
+            }
+        }
+    }
+
+    /// The program and its arguments, split into the program to run and the rest
+    pub fn program(&self) -> Result<(String, Vec<String>), anyhow::Error> {
+        let mut args = self.args()?.into_iter();
+        let program = args.next().ok_or_else(|| anyhow!("The command is empty"))?;
+        Ok((program, args.collect()))
+    }
+
+    /// The command line as it would be written in a shell
+    pub fn line(&self) -> String {
+        match self {
+            ProjectCommand::Line(line) => line.clone(),
+            ProjectCommand::Args(args) => shell_words::join(args),
+        }
+    }
+
+    /// The arguments to run, wrapped in the shell if there is one. The command line is given to
+    /// the shell as written, so it can use pipes, `&&` and the like
+    pub fn resolve(&self, shell: Option<&Shell>) -> Result<Self, anyhow::Error> {
+        let program = match shell {
+            None | Some(Shell::Enabled(false)) => return Ok(ProjectCommand::Args(self.args()?)),
+            Some(Shell::Enabled(true)) => user_shell(),
+            Some(Shell::Program(program)) => program.clone(),
+        };
+
+        Ok(ProjectCommand::Args(vec![
+            program,
+            "-c".to_string(),
+            self.line(),
+        ]))
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut String> {
+        match self {
+            ProjectCommand::Line(line) => std::slice::from_mut(line).iter_mut(),
+            ProjectCommand::Args(args) => args.iter_mut(),
+        }
+    }
+}
+
+fn user_shell() -> String {
+    std::env::var("SHELL")
+        .ok()
+        .filter(|shell| !shell.is_empty())
+        .unwrap_or_else(|| DEFAULT_SHELL.to_string())
+}
@@ -1,18 +1,42 @@
-use std::{fs::File, path::PathBuf, str::FromStr};
+use std::{
+    collections::{HashMap, HashSet},
+    fs::{File, OpenOptions},
+    io::{ErrorKind, Write},
+    path::{Path, PathBuf},
+    str::FromStr,
+    time::SystemTime,
+};
 
 use anyhow::{anyhow, Context};
 use itertools::{Either, Itertools};
 use serde::Deserialize;
 
 use crate::{
+    command::Shell,
+    daemon::Client,
+    env::interpolate,
+    graph::DependencyGraph,
+    history::{unix_secs, Run},
+    libc::Signal,
     project::{Project, RunningProject, WorkerProject},
     ActionArg, ActionArgRunning,
 };
 
 const CONFIG_FILE: &str = ".worker.toml";
+const USER_CONFIG_FILE: &str = "worker/config.toml";
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Debug, Default)]
 pub struct Config {
+    /// Other config files to merge into this one, or directories containing them
+    #[serde(default)]
+    pub include: Vec<String>,
+    /// Merge the config file in the directories above as well
+    #[serde(default)]
+    pub inherit: bool,
+    /// Variables set for every project
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+    #[serde(default)]
     pub project: Vec<Project>,
 }
 
@@ -35,7 +59,7 @@ impl FromStr for ActionArg {
         if !projects_in_group.is_empty() {
             Ok(ActionArg::Group(projects_in_group))
         } else if let Some(project) = config.projects.iter().find(|it| it.name == s) {
-            Ok(ActionArg::Project(project.clone()))
+            Ok(ActionArg::Project(Box::new(project.clone())))
         } else {
             let project_names: Vec<String> =
                 config.projects.iter().map(|p| p.name.clone()).collect();
@@ -77,7 +101,7 @@ impl FromStr for ActionArgRunning {
         if !projects_in_group.is_empty() {
             Ok(ActionArgRunning::Group(projects_in_group))
         } else if let Some(project) = running.iter().find(|it| it.name == s) {
-            Ok(ActionArgRunning::Project(project.clone()))
+            Ok(ActionArgRunning::Project(Box::new(project.clone())))
         } else {
             if let Some(project) = config.projects.into_iter().find(|it| it.name == s) {
                 println!("{} is not running", project);
@@ -92,35 +116,110 @@ impl FromStr for ActionArgRunning {
 #[derive(Clone)]
 pub struct WorkerConfig {
     pub projects: Vec<Project>,
+    pub env: HashMap<String, String>,
+    pub graph: DependencyGraph,
     state_dir: PathBuf,
     log_dir: PathBuf,
+    history_dir: PathBuf,
+    stdin_dir: PathBuf,
+    socket_file: PathBuf,
+    worker_dir: PathBuf,
 }
 
 impl WorkerConfig {
     pub fn new() -> Result<Self, anyhow::Error> {
-        let base_dir = find_config_dir()?.context("Couldn't find config dir")?;
-        let config_string = std::fs::read_to_string(base_dir.join(CONFIG_FILE))?;
+        let (base_dir, files) = config_files()?;
+
+        let mut config = Config {
+            env: merged_env(&files),
+            ..Default::default()
+        };
+        for file in files {
+            merge_config(&mut config, file)?;
+        }
+
+        Self::with_projects(&base_dir, config.project, config.env)
+    }
 
+    /// Config of already resolved projects, which keeps their state in `.worker` in `base_dir`
+    pub fn with_projects(
+        base_dir: &Path,
+        projects: Vec<Project>,
+        env: HashMap<String, String>,
+    ) -> Result<Self, anyhow::Error> {
         let state_dir = base_dir.join(".worker/state");
         let log_dir = base_dir.join(".worker/log");
+        let history_dir = base_dir.join(".worker/history");
+        let stdin_dir = base_dir.join(".worker/stdin");
 
         std::fs::create_dir_all(&state_dir)?;
         std::fs::create_dir_all(&log_dir)?;
+        std::fs::create_dir_all(&history_dir)?;
+        std::fs::create_dir_all(&stdin_dir)?;
 
-        // Deserialize the TOML string into the Config struct
-        let config: Config = toml::from_str(&config_string)?;
+        let graph = DependencyGraph::new(&projects)?;
 
         Ok(Self {
-            projects: config.project,
+            graph,
+            projects,
+            env,
             state_dir,
             log_dir,
+            history_dir,
+            stdin_dir,
+            socket_file: base_dir.join(".worker/worker.sock"),
+            worker_dir: base_dir.join(".worker"),
         })
     }
 
+    /// Directory with the state, logs and history of the projects
+    pub fn worker_dir(&self) -> &Path {
+        &self.worker_dir
+    }
+
+    /// Socket the daemon of this config directory listens on
+    pub fn socket_file(&self) -> &Path {
+        &self.socket_file
+    }
+
     pub fn log_file<T: WorkerProject>(&self, project: &T) -> PathBuf {
         self.log_dir.join(project.name())
     }
 
+    /// FIFO the supervisor reads the stdin of the project from, when it accepts input
+    pub fn stdin_file<T: WorkerProject>(&self, project: &T) -> PathBuf {
+        self.stdin_dir.join(project.name())
+    }
+
+    pub fn record_run<T: WorkerProject>(
+        &self,
+        project: &T,
+        run: &Run,
+    ) -> Result<(), anyhow::Error> {
+        let mut file = OpenOptions::new()
+            .append(true)
+            .create(true)
+            .open(self.history_dir.join(project.name()))?;
+
+        writeln!(file, "{}", serde_json::to_string(run)?)?;
+
+        Ok(())
+    }
+
+    /// Recorded runs of the project, oldest first
+    pub fn history(&self, name: &str) -> Result<Vec<Run>, anyhow::Error> {
+        let content = match std::fs::read_to_string(self.history_dir.join(name)) {
+            Ok(content) => content,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(vec![]),
+            Err(e) => return Err(e.into()),
+        };
+
+        Ok(content
+            .lines()
+            .filter_map(|line| serde_json::from_str(line).ok())
+            .collect())
+    }
+
     pub fn get_state(&self, name: &str) -> Result<Option<RunningProject>, anyhow::Error> {
         let project = std::fs::read_dir(self.state_dir.as_path())?.find_map(|entry| {
             let path = entry.ok()?.path();
@@ -132,17 +231,7 @@ impl WorkerConfig {
                     .context("Couldn't parse project from state file")
                     .ok()?;
 
-                Some(RunningProject {
-                    name: project.name,
-                    command: project.command,
-                    cwd: project.cwd,
-                    display: project.display,
-                    stop_signal: project.stop_signal,
-                    envs: project.envs,
-                    group: project.group,
-                    pid: pid.parse::<i32>().ok()?,
-                    dependencies: project.dependencies,
-                })
+                Some(RunningProject::new(project, pid.parse::<i32>().ok()?))
             } else {
                 None
             }
@@ -161,8 +250,72 @@ impl WorkerConfig {
         Ok(())
     }
 
-    // Try to get vec of running projects. Try to remove the state file if the process is not running
+    /// When the project was started, which is when its state file was written
+    pub fn started_at(&self, project: &RunningProject) -> Option<SystemTime> {
+        std::fs::metadata(
+            self.state_dir
+                .join(format!("{}-{}", project.name, project.pid)),
+        )
+        .and_then(|metadata| metadata.modified())
+        .ok()
+    }
+
+    /// Records the run of a project that was killed together with its supervisor, since the
+    /// supervisor can't record it itself then
+    pub fn record_killed(
+        &self,
+        project: &RunningProject,
+        started_at: SystemTime,
+        killed_at: SystemTime,
+    ) -> Result<(), anyhow::Error> {
+        let started_at = unix_secs(started_at);
+        let last = self.history(&project.name)?.pop();
+
+        // The supervisor got to record the run before it was killed
+        if last
+            .as_ref()
+            .is_some_and(|run| run.ended_at >= unix_secs(killed_at))
+        {
+            return Ok(());
+        }
+
+        // The supervisor may have restarted the command since the project was started
+        let (started_at, crashes) = match last {
+            Some(run) if run.ended_at >= started_at => (run.ended_at, run.crashes),
+            _ => (started_at, 0),
+        };
+
+        let signal = project.kill_signal.clone().unwrap_or(Signal::SIGKILL);
+        self.record_run(
+            project,
+            &Run {
+                // The pid of the command isn't known outside of the supervisor
+                pid: project.pid as u32,
+                started_at,
+                ended_at: unix_secs(SystemTime::now()),
+                code: None,
+                signal: Some(signal as i32),
+                stopped: true,
+                crashes,
+            },
+        )
+    }
+
+    pub fn remove_state(&self, name: &str, pid: i32) {
+        let _ = std::fs::remove_file(self.state_dir.join(format!("{name}-{pid}")));
+    }
+
+    /// The running projects, as known by the daemon if there is one. Otherwise they are found by
+    /// scanning the state files
     pub fn running(&self) -> Result<Vec<RunningProject>, anyhow::Error> {
+        match Client::connect(self) {
+            Some(daemon) => daemon.running(),
+            None => self.scan_running(),
+        }
+    }
+
+    // Try to get vec of running projects. Try to remove the state file if the process is not running
+    pub fn scan_running(&self) -> Result<Vec<RunningProject>, anyhow::Error> {
         let projects = std::fs::read_dir(self.state_dir.as_path())?
             .filter_map(|entry| {
                 let path = entry.ok()?.path();
@@ -184,6 +337,67 @@ impl WorkerConfig {
         Ok(projects)
     }
 
+    /// The projects together with all of their dependencies, in the layers they should be started
+    pub fn start_order(&self, projects: &[Project]) -> Vec<Vec<Project>> {
+        let names: Vec<_> = projects.iter().map(|p| p.name.as_str()).collect();
+
+        self.graph
+            .layers(&names)
+            .into_iter()
+            .map(|layer| {
+                layer
+                    .into_iter()
+                    .filter_map(|name| {
+                        projects
+                            .iter()
+                            .chain(self.projects.iter())
+                            .find(|p| p.name == name)
+                            .cloned()
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// The projects together with every running project depending on them, in the layers they
+    /// should be stopped. Dependents come before their dependencies
+    pub fn stop_order(
+        &self,
+        projects: &[RunningProject],
+    ) -> Result<Vec<Vec<RunningProject>>, anyhow::Error> {
+        let names: HashSet<_> = projects
+            .iter()
+            .flat_map(|p| {
+                let mut names = self.graph.dependents(&p.name);
+                names.push(&p.name);
+                names
+            })
+            .collect();
+
+        let running: Vec<_> = self
+            .running()?
+            .into_iter()
+            .filter(|p| names.contains(p.name.as_str()))
+            .collect();
+
+        let running_names: Vec<_> = running.iter().map(|p| p.name.as_str()).collect();
+
+        Ok(self
+            .graph
+            .layers(&running_names)
+            .into_iter()
+            .rev()
+            .map(|layer| {
+                running
+                    .iter()
+                    .filter(|p| layer.contains(&p.name))
+                    .cloned()
+                    .collect::<Vec<_>>()
+            })
+            .filter(|layer| !layer.is_empty())
+            .collect())
+    }
+
     pub fn partition_projects<T>(
         &self,
         projects: Vec<T>,
@@ -204,9 +418,227 @@ impl WorkerConfig {
     }
 }
 
+/// A config file, as read from disk
+pub struct ConfigFile {
+    pub path: PathBuf,
+    pub content: String,
+    pub config: Config,
+}
+
+impl ConfigFile {
+    fn read(path: PathBuf) -> Result<Self, anyhow::Error> {
+        let content = std::fs::read_to_string(&path)
+            .with_context(|| format!("Couldn't read {}", path.display()))?;
+        let config = toml::from_str(&content)
+            .with_context(|| format!("Couldn't parse {}", path.display()))?;
+
+        Ok(Self {
+            path,
+            content,
+            config,
+        })
+    }
+
+    /// Directory the paths in the file are relative to
+    pub fn dir(&self) -> &Path {
+        self.path.parent().unwrap_or(Path::new("/"))
+    }
+}
+
+/// The config files in the order they are merged, together with the directory the projects are
+/// managed from. Later files take precedence, so the nearest config comes last, and every file
+/// comes after the files it includes
+pub fn config_files() -> Result<(PathBuf, Vec<ConfigFile>), anyhow::Error> {
+    let mut base_dir =
+        find_config_dir(std::env::current_dir()?)?.context("Couldn't find config dir")?;
+
+    // A config with `inherit` also uses the config in the directories above it, and the topmost
+    // one the config of the user. The projects are managed from the topmost directory
+    let mut chain = vec![ConfigFile::read(base_dir.join(CONFIG_FILE))?];
+    while chain.last().is_some_and(|file| file.config.inherit) {
+        let parent = match base_dir.parent() {
+            Some(parent) => find_config_dir(parent.to_path_buf())?,
+            None => None,
+        };
+
+        match parent {
+            Some(dir) => {
+                chain.push(ConfigFile::read(dir.join(CONFIG_FILE))?);
+                base_dir = dir;
+            }
+            None => {
+                if let Some(path) = user_config_file().filter(|path| path.exists()) {
+                    chain.push(ConfigFile::read(path)?);
+                }
+                break;
+            }
+        }
+    }
+
+    let mut files = vec![];
+    let mut loaded = HashSet::new();
+    for file in chain.into_iter().rev() {
+        with_includes(file, &mut files, &mut loaded)?;
+    }
+
+    Ok((base_dir, files))
+}
+
+fn with_includes(
+    file: ConfigFile,
+    files: &mut Vec<ConfigFile>,
+    loaded: &mut HashSet<PathBuf>,
+) -> Result<(), anyhow::Error> {
+    if !loaded.insert(file.path.canonicalize()?) {
+        return Ok(());
+    }
+
+    for include in file.config.include.iter() {
+        let mut include: PathBuf = file.dir().join(include).components().collect();
+        if include.is_dir() {
+            include.push(CONFIG_FILE);
+        }
+
+        if !include.exists() {
+            return Err(anyhow!(
+                "Couldn't include {} from {}",
+                include.display(),
+                file.path.display()
+            ));
+        }
+        with_includes(ConfigFile::read(include)?, files, loaded)?;
+    }
+
+    files.push(file);
+
+    Ok(())
+}
+
+/// The `[env]` tables of the config files, where a later file overrides an earlier one
+pub fn merged_env(files: &[ConfigFile]) -> HashMap<String, String> {
+    files
+        .iter()
+        .flat_map(|file| file.config.env.clone())
+        .collect()
+}
+
+// A project replaces a project with the same name from a file merged earlier. The `env` of the
+// merged config already holds the `[env]` of every file, so any of them can be used in variables
+fn merge_config(merged: &mut Config, file: ConfigFile) -> Result<(), anyhow::Error> {
+    let dir = file.dir().to_path_buf();
+
+    for mut project in file.config.project {
+        // A project that can't be resolved only fails when it is started, so a mistake in one
+        // project doesn't break every command
+        if let Err(e) = resolve_project(&mut project, &dir, &merged.env) {
+            project.error = Some(format!("{e:#}"));
+        }
+
+        match merged.project.iter_mut().find(|p| p.name == project.name) {
+            Some(existing) => *existing = project,
+            None => merged.project.push(project),
+        }
+    }
+
+    Ok(())
+}
+
+/// Expands the variables of the project, and resolves its `cwd` against the directory of the
+/// config file it is defined in
+pub fn resolve_project(
+    project: &mut Project,
+    config_dir: &Path,
+    env: &HashMap<String, String>,
+) -> Result<(), anyhow::Error> {
+    // Split the command once, so the state and the supervisor only see the arguments to run
+    project.command = project
+        .command
+        .resolve(project.shell.as_ref())
+        .with_context(|| format!("Invalid command of project {}", project.name))?;
+
+    expand_variables(project, config_dir, env)
+        .with_context(|| format!("Couldn't expand variables in project {}", project.name))?;
+    project.shell = None;
+
+    resolve_cwd(project, config_dir)?;
+
+    Ok(())
+}
+
+// `~/.config/worker/config.toml`, or in `$XDG_CONFIG_HOME` if set
+fn user_config_file() -> Option<PathBuf> {
+    let config_home = std::env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".config")))?;
+
+    Some(config_home.join(USER_CONFIG_FILE))
+}
+
+// Expands the variables in the fields of the project that usually differ between machines. A
+// variable is looked up in the `envs` of the project, then in `[env]` and then in the environment of
+// worker, and `${config_dir}` and `${project.name}` can be used as well. The command has already
+// been split, so a value is always a single argument. In the script of a command run through a
+// shell, values are quoted to stay a single word, and the variables that aren't set are kept, so
+// the shell can expand them from the environment of the command
+fn expand_variables(
+    project: &mut Project,
+    config_dir: &Path,
+    env: &HashMap<String, String>,
+) -> Result<(), anyhow::Error> {
+    let name = project.name.clone();
+    let builtin = |var: &str| match var {
+        "config_dir" => Some(config_dir.to_string_lossy().to_string()),
+        "project.name" => Some(name.clone()),
+        var => env.get(var).cloned().or_else(|| std::env::var(var).ok()),
+    };
+
+    for value in project.envs.iter_mut().flat_map(|envs| envs.values_mut()) {
+        *value = interpolate(value, builtin, false)?;
+    }
+
+    let envs = project.envs.clone().unwrap_or_default();
+    let lookup = |var: &str| envs.get(var).cloned().or_else(|| builtin(var));
+
+    let through_shell = matches!(
+        project.shell,
+        Some(Shell::Enabled(true) | Shell::Program(_))
+    );
+    // The script comes last, after the shell and `-c`
+    let script = project.command.iter_mut().count().saturating_sub(1);
+    for (i, arg) in project.command.iter_mut().enumerate() {
+        *arg = interpolate(arg, lookup, through_shell && i == script)?;
+    }
+    project.cwd = interpolate(&project.cwd, lookup, false)?;
+
+    Ok(())
+}
+
+// Relative paths are resolved against the directory of the config file, and a missing `cwd` is the
+// directory itself, so it doesn't matter where worker runs from
+fn resolve_cwd(project: &mut Project, config_dir: &Path) -> Result<(), anyhow::Error> {
+    let cwd: PathBuf = config_dir.join(&project.cwd).components().collect();
+
+    if !cwd.is_dir() {
+        return Err(anyhow!(
+            "The cwd of {} is not a directory: {}{}",
+            project.name,
+            cwd.display(),
+            if Path::new(&project.cwd).is_relative() {
+                format!(" (relative to {})", config_dir.display())
+            } else {
+                String::new()
+            }
+        ));
+    }
+
+    project.cwd = cwd.to_string_lossy().to_string();
+
+    Ok(())
+}
+
 // Scan root directories until we hopefully find the config file
-fn find_config_dir() -> Result<Option<PathBuf>, anyhow::Error> {
-    let mut dir = std::env::current_dir()?;
+fn find_config_dir(mut dir: PathBuf) -> Result<Option<PathBuf>, anyhow::Error> {
     loop {
         if dir.join(CONFIG_FILE).exists() {
             return Ok(Some(dir));
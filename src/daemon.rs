@@ -0,0 +1,320 @@
+use std::{
+    collections::HashMap,
+    io::{BufRead, BufReader, ErrorKind, Write},
+    os::unix::{
+        net::{UnixListener, UnixStream},
+        process::CommandExt,
+    },
+    process::Stdio,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+use anyhow::{anyhow, Context};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config::WorkerConfig,
+    libc::{handle_signal, has_processes_running, setsid, stop_pg, Signal},
+    log::{LogFilter, LogLine, LogReader},
+    project::{Project, RunningProject, WorkerProject},
+};
+
+static SHUTDOWN_REQUESTED: AtomicBool = AtomicBool::new(false);
+
+extern "C" fn request_shutdown(_: i32) {
+    SHUTDOWN_REQUESTED.store(true, Ordering::SeqCst);
+}
+
+/// Request sent from the CLI to the daemon. Every connection carries one request and its response,
+/// each as a line of JSON
+#[derive(Deserialize, Serialize, Debug)]
+pub enum Request {
+    Start(Box<Project>),
+    Running,
+    /// Sends a signal to the process group of a project, which is how projects are stopped
+    Signal {
+        name: String,
+        signal: Signal,
+    },
+    /// The last lines of the log of a project that pass the filter
+    Logs {
+        name: String,
+        number: usize,
+        filter: LogFilter,
+    },
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub enum Response {
+    Started,
+    Running(Vec<RunningProject>),
+    Signaled,
+    /// The lines, and where in the log file the next line starts
+    Logs {
+        lines: Vec<String>,
+        pos: u64,
+    },
+    Error(String),
+}
+
+/// Connection to the daemon of the config directory
+pub struct Client {
+    stream: UnixStream,
+}
+
+impl Client {
+    /// Connects to the daemon, or returns `None` if no daemon is running
+    pub fn connect(config: &WorkerConfig) -> Option<Self> {
+        let stream = UnixStream::connect(config.socket_file()).ok()?;
+        Some(Self { stream })
+    }
+
+    pub fn start(self, project: &Project) -> Result<(), anyhow::Error> {
+        match self.request(&Request::Start(Box::new(project.clone())))? {
+            Response::Started => Ok(()),
+            response => Err(unexpected(response)),
+        }
+    }
+
+    pub fn running(self) -> Result<Vec<RunningProject>, anyhow::Error> {
+        match self.request(&Request::Running)? {
+            Response::Running(projects) => Ok(projects),
+            response => Err(unexpected(response)),
+        }
+    }
+
+    pub fn signal(self, name: &str, signal: &Signal) -> Result<(), anyhow::Error> {
+        let request = Request::Signal {
+            name: name.to_string(),
+            signal: signal.clone(),
+        };
+        match self.request(&request)? {
+            Response::Signaled => Ok(()),
+            response => Err(unexpected(response)),
+        }
+    }
+
+    pub fn logs(
+        self,
+        name: &str,
+        number: usize,
+        filter: &LogFilter,
+    ) -> Result<(Vec<String>, u64), anyhow::Error> {
+        let request = Request::Logs {
+            name: name.to_string(),
+            number,
+            filter: filter.clone(),
+        };
+        match self.request(&request)? {
+            Response::Logs { lines, pos } => Ok((lines, pos)),
+            response => Err(unexpected(response)),
+        }
+    }
+
+    fn request(mut self, request: &Request) -> Result<Response, anyhow::Error> {
+        writeln!(self.stream, "{}", serde_json::to_string(request)?)?;
+
+        let mut line = String::new();
+        BufReader::new(&self.stream).read_line(&mut line)?;
+        Ok(serde_json::from_str(&line)?)
+    }
+}
+
+fn unexpected(response: Response) -> anyhow::Error {
+    match response {
+        Response::Error(e) => anyhow!(e),
+        response => anyhow!("Unexpected response from the daemon: {response:?}"),
+    }
+}
+
+/// Owns the supervisors of every project started through it, and learns right away when one of
+/// them exits, instead of every command scanning the process table
+struct Daemon {
+    config: WorkerConfig,
+    running: Mutex<HashMap<String, RunningProject>>,
+}
+
+/// Runs the daemon in the foreground until it gets SIGINT or SIGTERM. The projects keep running
+/// when the daemon exits
+pub fn serve(config: WorkerConfig) -> Result<(), anyhow::Error> {
+    let socket = config.socket_file().to_path_buf();
+    if UnixStream::connect(&socket).is_ok() {
+        return Err(anyhow!(
+            "A daemon is already running on {}",
+            socket.display()
+        ));
+    }
+
+    // Left behind by a daemon that didn't exit cleanly
+    let _ = std::fs::remove_file(&socket);
+    let listener = UnixListener::bind(&socket)?;
+    listener.set_nonblocking(true)?;
+
+    for signal in [Signal::SIGINT, Signal::SIGTERM] {
+        handle_signal(&signal, request_shutdown)
+            .map_err(|e| anyhow!("Couldn't install signal handler: {e}"))?;
+    }
+
+    let daemon = Arc::new(Daemon {
+        running: Mutex::new(HashMap::new()),
+        config,
+    });
+
+    // Projects started before the daemon aren't its children, so they can only be polled
+    for project in daemon.config.scan_running()? {
+        daemon.adopt(project);
+    }
+
+    while !SHUTDOWN_REQUESTED.load(Ordering::SeqCst) {
+        match listener.accept() {
+            Ok((stream, _)) => {
+                let daemon = daemon.clone();
+                std::thread::spawn(move || daemon.handle(stream));
+            }
+            Err(e) if e.kind() == ErrorKind::WouldBlock => {
+                std::thread::sleep(Duration::from_millis(100));
+            }
+            Err(e) => eprintln!("worker: couldn't accept connection: {e}"),
+        }
+    }
+
+    std::fs::remove_file(&socket)?;
+
+    Ok(())
+}
+
+impl Daemon {
+    fn handle(self: Arc<Self>, stream: UnixStream) -> Result<(), anyhow::Error> {
+        stream.set_nonblocking(false)?;
+
+        let mut line = String::new();
+        BufReader::new(&stream).read_line(&mut line)?;
+
+        let response = match serde_json::from_str(&line) {
+            Ok(Request::Start(project)) => match self.start(*project) {
+                Ok(()) => Response::Started,
+                Err(e) => Response::Error(format!("{e:#}")),
+            },
+            Ok(Request::Running) => {
+                Response::Running(self.running.lock().unwrap().values().cloned().collect())
+            }
+            Ok(Request::Signal { name, signal }) => match self.signal(&name, &signal) {
+                Ok(()) => Response::Signaled,
+                Err(e) => Response::Error(format!("{e:#}")),
+            },
+            Ok(Request::Logs {
+                name,
+                number,
+                filter,
+            }) => match self.logs(&name, number, &filter) {
+                Ok((lines, pos)) => Response::Logs { lines, pos },
+                Err(e) => Response::Error(format!("{e:#}")),
+            },
+            Err(e) => Response::Error(format!("Invalid request: {e}")),
+        };
+
+        writeln!(&stream, "{}", serde_json::to_string(&response)?)?;
+
+        Ok(())
+    }
+
+    fn start(self: &Arc<Self>, project: Project) -> Result<(), anyhow::Error> {
+        let mut running = self.running.lock().unwrap();
+        if running.contains_key(&project.name) {
+            return Err(anyhow!("{project} is already running"));
+        }
+
+        let mut cmd = std::process::Command::new(std::env::current_exe()?);
+        cmd.arg("supervise")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null());
+
+        // The supervisor gets its own session, just like when started without the daemon
+        unsafe {
+            cmd.pre_exec(|| {
+                setsid().map_err(|_| std::io::Error::last_os_error())?;
+                Ok(())
+            });
+        }
+
+        let mut child = cmd.spawn()?;
+        // Handed over on stdin, since anyone can read the command line, and the project has its
+        // envs. Closed right after, so the supervisor knows it has all of it
+        serde_json::to_writer(child.stdin.take().expect("Stdin is piped"), &project)?;
+        let pid = child.id() as i32;
+        self.config.store_state(pid, &project)?;
+        running.insert(
+            project.name.clone(),
+            RunningProject::new(project.clone(), pid),
+        );
+
+        let daemon = self.clone();
+        std::thread::spawn(move || {
+            let _ = child.wait();
+            daemon.exited(&project.name, pid);
+        });
+
+        Ok(())
+    }
+
+    // Only signals the process groups of its own projects, so a stale pid of an exited project is
+    // never reused by accident
+    fn signal(&self, name: &str, signal: &Signal) -> Result<(), anyhow::Error> {
+        let running = self.running.lock().unwrap();
+        let project = running
+            .get(name)
+            .ok_or_else(|| anyhow!("{name} is not running"))?;
+        stop_pg(project.pid, signal).map_err(|e| anyhow!("Couldn't signal {name}: {e}"))
+    }
+
+    fn logs(
+        &self,
+        name: &str,
+        number: usize,
+        filter: &LogFilter,
+    ) -> Result<(Vec<String>, u64), anyhow::Error> {
+        let project = self
+            .running
+            .lock()
+            .unwrap()
+            .get(name)
+            .cloned()
+            .ok_or_else(|| anyhow!("{name} is not running"))?;
+
+        let timestamps = project.logs_timestamps();
+        let mut reader = LogReader::new(self.config.log_file(&project));
+        let lines = reader
+            .last_lines(number, |line| {
+                filter.matches(&LogLine::parse(line, timestamps))
+            })
+            .with_context(|| format!("Couldn't read the log of {name}"))?;
+
+        Ok((lines, reader.position()))
+    }
+
+    fn adopt(self: &Arc<Self>, project: RunningProject) {
+        let (name, pid) = (project.name.clone(), project.pid);
+        self.running.lock().unwrap().insert(name.clone(), project);
+
+        let daemon = self.clone();
+        std::thread::spawn(move || {
+            while has_processes_running(pid) {
+                std::thread::sleep(Duration::from_secs(1));
+            }
+            daemon.exited(&name, pid);
+        });
+    }
+
+    fn exited(&self, name: &str, pid: i32) {
+        let mut running = self.running.lock().unwrap();
+        if running.get(name).is_some_and(|p| p.pid == pid) {
+            running.remove(name);
+            self.config.remove_state(name, pid);
+        }
+    }
+}
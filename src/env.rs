@@ -0,0 +1,169 @@
+use std::{collections::HashMap, path::Path};
+
+use anyhow::{anyhow, Context};
+use serde::{Deserialize, Serialize};
+
+use crate::{config::WorkerConfig, project::Project};
+
+/// One or more env files. Deserialized from either a string or a list of strings
+#[derive(Deserialize, Serialize, Clone, Debug, Eq, PartialEq)]
+#[serde(from = "EnvFilesValue", into = "Vec<String>")]
+pub struct EnvFiles(pub Vec<String>);
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum EnvFilesValue {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl From<EnvFilesValue> for EnvFiles {
+    fn from(value: EnvFilesValue) -> Self {
+        match value {
+            EnvFilesValue::One(file) => EnvFiles(vec![file]),
+            EnvFilesValue::Many(files) => EnvFiles(files),
+        }
+    }
+}
+
+impl From<EnvFiles> for Vec<String> {
+    fn from(value: EnvFiles) -> Self {
+        value.0
+    }
+}
+
+/// The variables set for the command of the project, on top of the environment of worker. Later
+/// layers override earlier ones:
+///
+/// 1. The `[env]` table of the config file
+/// 2. The `env_file`s of the project, in the order they are listed
+/// 3. The `envs` of the project
+///
+/// The env files are read every time the project starts, so changes apply on the next restart
+pub fn project_env(
+    project: &Project,
+    config: &WorkerConfig,
+) -> Result<HashMap<String, String>, anyhow::Error> {
+    let mut env = config.env.clone();
+
+    for file in project.env_file.iter().flat_map(|files| files.0.iter()) {
+        let path = Path::new(&project.cwd).join(file);
+        let content = std::fs::read_to_string(&path)
+            .with_context(|| format!("Couldn't read env file {}", path.display()))?;
+        let vars = parse_env_file(&content)
+            .with_context(|| format!("Invalid env file {}", path.display()))?;
+        env.extend(vars);
+    }
+
+    env.extend(project.envs.clone().unwrap_or_default());
+
+    Ok(env)
+}
+
+/// Parses the `KEY=value` lines of a `.env` file. Lines may start with `export`, and values may
+/// be quoted. Escape sequences are only expanded in double quotes, like in a shell
+pub fn parse_env_file(content: &str) -> Result<Vec<(String, String)>, anyhow::Error> {
+    let mut vars = vec![];
+
+    for (i, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let line = line.strip_prefix("export ").unwrap_or(line);
+        let (key, value) = line
+            .split_once('=')
+            .ok_or_else(|| anyhow!("Expected KEY=value on line {}", i + 1))?;
+
+        let key = key.trim();
+        if key.is_empty() || key.contains(char::is_whitespace) {
+            return Err(anyhow!("Invalid variable name {key:?} on line {}", i + 1));
+        }
+
+        let value = parse_value(value.trim())
+            .ok_or_else(|| anyhow!("Unterminated quote on line {}", i + 1))?;
+
+        vars.push((key.to_string(), value));
+    }
+
+    Ok(vars)
+}
+
+fn parse_value(value: &str) -> Option<String> {
+    if let Some(rest) = value.strip_prefix('\'') {
+        let end = rest.find('\'')?;
+        return Some(rest[..end].to_string());
+    }
+
+    if let Some(rest) = value.strip_prefix('"') {
+        let mut parsed = String::new();
+        let mut chars = rest.chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '"' => return Some(parsed),
+                '\\' => match chars.next()? {
+                    'n' => parsed.push('\n'),
+                    't' => parsed.push('\t'),
+                    c => parsed.push(c),
+                },
+                c => parsed.push(c),
+            }
+        }
+        return None;
+    }
+
+    // Unquoted values end at a comment
+    let value = match value.find(" #") {
+        Some(i) => &value[..i],
+        None => value,
+    };
+    Some(value.trim_end().to_string())
+}
+
+/// Expands `${VAR}` and `${VAR:-default}` in a value from the config file, where the default is
+/// used when the variable is unset or empty. `$${` is kept as a literal `${`. Unset variables
+/// without a default are an error. In a `script` for a shell, they are kept for the shell to
+/// expand instead, and the values are quoted, so each stays a single word
+pub fn interpolate(
+    value: &str,
+    lookup: impl Fn(&str) -> Option<String>,
+    script: bool,
+) -> Result<String, anyhow::Error> {
+    let mut expanded = String::new();
+    let mut rest = value;
+
+    while let Some(start) = rest.find("${") {
+        if rest[..start].ends_with('$') {
+            expanded.push_str(&rest[..start - 1]);
+            expanded.push_str("${");
+            rest = &rest[start + 2..];
+            continue;
+        }
+
+        expanded.push_str(&rest[..start]);
+        let end = rest[start..]
+            .find('}')
+            .ok_or_else(|| anyhow!("Missing }} after ${{ in {value:?}"))?;
+        let expression = &rest[start + 2..start + end];
+
+        let (name, default) = match expression.split_once(":-") {
+            Some((name, default)) => (name, Some(default)),
+            None => (expression, None),
+        };
+
+        match (lookup(name), default) {
+            (Some(v), Some(default)) if v.is_empty() => expanded.push_str(default),
+            (Some(v), _) if script => expanded.push_str(&shell_words::quote(&v)),
+            (Some(v), _) => expanded.push_str(&v),
+            (None, _) if script => expanded.push_str(&rest[start..=start + end]),
+            (None, Some(default)) => expanded.push_str(default),
+            (None, None) => return Err(anyhow!("Variable {name} is not set")),
+        }
+
+        rest = &rest[start + end + 1..];
+    }
+
+    expanded.push_str(rest);
+    Ok(expanded)
+}
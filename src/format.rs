@@ -0,0 +1,180 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use clap::ValueEnum;
+use serde::Serialize;
+
+use crate::{
+    history::Run,
+    libc::SessionUsage,
+    project::{Project, RunningProject},
+};
+
+/// Output format of the commands printing projects
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Format {
+    #[default]
+    Text,
+    Json,
+    Table,
+}
+
+/// Project as printed by `status` and `list` with `--format json`
+#[derive(Serialize, Debug)]
+pub struct ProjectInfo {
+    pub name: String,
+    pub display: Option<String>,
+    pub group: Vec<String>,
+    pub pid: Option<i32>,
+    pub cwd: String,
+    pub command: Vec<String>,
+    /// Seconds since the project was started
+    pub uptime: Option<u64>,
+    pub dependencies: Vec<String>,
+    /// Seconds since the unix epoch
+    pub started_at: Option<u64>,
+    /// CPU usage in percent of one core, summed over all processes of the project
+    pub cpu: Option<f32>,
+    /// Resident memory in bytes, summed over all processes of the project
+    pub memory: Option<u64>,
+    pub processes: Option<usize>,
+    /// How the project exited the last time it ran, only set for projects that are not running
+    pub last_run: Option<Run>,
+}
+
+impl ProjectInfo {
+    pub fn new(
+        project: &Project,
+        running: Option<&RunningProject>,
+        usage: Option<&SessionUsage>,
+    ) -> Self {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+
+        Self {
+            name: project.name.clone(),
+            display: project.display.clone(),
+            group: project.group.clone().unwrap_or_default(),
+            pid: running.map(|p| p.pid),
+            cwd: project.cwd.clone(),
+            command: project
+                .command
+                .args()
+                .unwrap_or_else(|_| vec![project.command.line()]),
+            uptime: usage.map(|u| now.saturating_sub(u.started_at)),
+            dependencies: project.dependencies.clone().unwrap_or_default(),
+            started_at: usage.map(|u| u.started_at),
+            cpu: usage.map(|u| u.cpu),
+            memory: usage.map(|u| u.memory),
+            processes: usage.map(|u| u.processes),
+            last_run: None,
+        }
+    }
+
+    fn row(&self) -> Vec<String> {
+        vec![
+            self.name.clone(),
+            self.pid.map(|pid| pid.to_string()).unwrap_or_default(),
+            self.cpu.map(|cpu| format!("{cpu:.1}%")).unwrap_or_default(),
+            self.memory.map(format_bytes).unwrap_or_default(),
+            self.processes.map(|p| p.to_string()).unwrap_or_default(),
+            self.uptime
+                .map(|uptime| format_duration(Duration::from_secs(uptime)))
+                .unwrap_or_default(),
+            self.group.join(","),
+            self.command.join(" "),
+        ]
+    }
+}
+
+pub fn print_json(projects: &[ProjectInfo]) -> Result<(), anyhow::Error> {
+    println!("{}", serde_json::to_string_pretty(projects)?);
+    Ok(())
+}
+
+pub fn print_table(projects: &[ProjectInfo]) {
+    let header = [
+        "NAME", "PID", "CPU", "MEM", "PROCS", "UPTIME", "GROUP", "COMMAND",
+    ]
+    .map(String::from)
+    .to_vec();
+
+    let rows: Vec<_> = std::iter::once(header)
+        .chain(projects.iter().map(|p| p.row()))
+        .collect();
+
+    let widths: Vec<_> = (0..rows[0].len())
+        .map(|i| {
+            rows.iter()
+                .map(|row| row[i].chars().count())
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+
+    for row in rows {
+        let line = row
+            .iter()
+            .zip(widths.iter())
+            .map(|(cell, width)| format!("{cell:width$}"))
+            .collect::<Vec<_>>()
+            .join("  ");
+        println!("{}", line.trim_end());
+    }
+}
+
+/// Human readable size in bytes, e.g. `12.3M`
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["K", "M", "G", "T"];
+
+    if bytes < 1024 {
+        return format!("{bytes}B");
+    }
+
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+
+    format!("{size:.1}{}", UNITS[unit])
+}
+
+/// Relative time since a timestamp in seconds since the unix epoch, e.g. `3 minutes ago`
+pub fn format_ago(timestamp: u64) -> String {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let secs = now.saturating_sub(timestamp);
+
+    let (amount, unit) = match secs {
+        0..60 => (secs, "second"),
+        60..3600 => (secs / 60, "minute"),
+        3600..86400 => (secs / 3600, "hour"),
+        _ => (secs / 86400, "day"),
+    };
+
+    let plural = if amount == 1 { "" } else { "s" };
+    format!("{amount} {unit}{plural} ago")
+}
+
+/// Short human readable duration, e.g. `3m 12s` or `2d 4h`
+pub fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    let (days, hours, minutes, seconds) = (
+        secs / 86400,
+        secs % 86400 / 3600,
+        secs % 3600 / 60,
+        secs % 60,
+    );
+
+    match (days, hours, minutes) {
+        (0, 0, 0) => format!("{seconds}s"),
+        (0, 0, _) => format!("{minutes}m {seconds}s"),
+        (0, _, _) => format!("{hours}h {minutes}m"),
+        _ => format!("{days}d {hours}h"),
+    }
+}
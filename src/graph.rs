@@ -0,0 +1,178 @@
+use std::collections::{BTreeMap, HashMap};
+
+use anyhow::anyhow;
+
+use crate::project::Project;
+
+/// Dependencies between the projects in the config. Validated when the config is loaded, so it
+/// is guaranteed to only reference known projects and to not contain any cycles
+#[derive(Clone, Debug, Default)]
+pub struct DependencyGraph {
+    dependencies: BTreeMap<String, Vec<String>>,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Visit {
+    InProgress,
+    Done,
+}
+
+impl DependencyGraph {
+    pub fn new(projects: &[Project]) -> Result<Self, anyhow::Error> {
+        let dependencies: BTreeMap<_, _> = projects
+            .iter()
+            .map(|p| (p.name.clone(), p.dependencies.clone().unwrap_or_default()))
+            .collect();
+
+        for (name, deps) in dependencies.iter() {
+            if let Some(dep) = deps.iter().find(|dep| !dependencies.contains_key(*dep)) {
+                return Err(anyhow!("{name} depends on unknown project {dep}"));
+            }
+        }
+
+        let graph = Self { dependencies };
+
+        let mut visited = HashMap::new();
+        for name in graph.dependencies.keys() {
+            graph.find_cycle(name, &mut visited, &mut vec![])?;
+        }
+
+        Ok(graph)
+    }
+
+    fn find_cycle<'a>(
+        &'a self,
+        name: &'a str,
+        visited: &mut HashMap<&'a str, Visit>,
+        path: &mut Vec<&'a str>,
+    ) -> Result<(), anyhow::Error> {
+        path.push(name);
+        match visited.get(name) {
+            Some(Visit::Done) => {}
+            Some(Visit::InProgress) => {
+                let start = path.iter().position(|it| *it == name).unwrap_or_default();
+                return Err(anyhow!(
+                    "Dependency cycle between projects: {}",
+                    path[start..].join(" -> ")
+                ));
+            }
+            None => {
+                visited.insert(name, Visit::InProgress);
+                for dep in self.dependencies_of(name) {
+                    self.find_cycle(dep, visited, path)?;
+                }
+                visited.insert(name, Visit::Done);
+            }
+        }
+        path.pop();
+
+        Ok(())
+    }
+
+    pub fn dependencies_of(&self, name: &str) -> &[String] {
+        self.dependencies
+            .get(name)
+            .map(|deps| deps.as_slice())
+            .unwrap_or_default()
+    }
+
+    /// Every project that depends on the project, directly or transitively
+    pub fn dependents(&self, name: &str) -> Vec<&str> {
+        let mut dependents: Vec<&str> = vec![];
+        let mut stack = vec![name];
+
+        while let Some(current) = stack.pop() {
+            for (dependent, deps) in self.dependencies.iter() {
+                if deps.iter().any(|dep| dep == current)
+                    && !dependents.contains(&dependent.as_str())
+                {
+                    dependents.push(dependent);
+                    stack.push(dependent);
+                }
+            }
+        }
+
+        dependents
+    }
+
+    /// The projects together with all of their transitive dependencies, grouped in layers. A
+    /// project only depends on projects in earlier layers, so every project within a layer can
+    /// be started at the same time. Names not in the graph are treated as having no dependencies
+    pub fn layers(&self, names: &[&str]) -> Vec<Vec<String>> {
+        let mut depths = BTreeMap::new();
+        for name in names {
+            self.depth(name, &mut depths);
+        }
+
+        let mut layers = vec![vec![]; depths.values().max().map_or(0, |max| max + 1)];
+        for (name, depth) in depths {
+            layers[depth].push(name.to_string());
+        }
+
+        layers
+    }
+
+    fn depth<'a>(&'a self, name: &'a str, depths: &mut BTreeMap<&'a str, usize>) -> usize {
+        if let Some(depth) = depths.get(name) {
+            return *depth;
+        }
+
+        let depth = self
+            .dependencies_of(name)
+            .iter()
+            .map(|dep| self.depth(dep, depths) + 1)
+            .max()
+            .unwrap_or(0);
+
+        depths.insert(name, depth);
+        depth
+    }
+
+    /// Projects that no other project depends on
+    pub fn roots(&self) -> Vec<&str> {
+        self.dependencies
+            .keys()
+            .filter(|name| !self.dependencies.values().flatten().any(|dep| dep == *name))
+            .map(|name| name.as_str())
+            .collect()
+    }
+
+    pub fn tree(&self, roots: &[&str]) -> String {
+        let mut out = String::new();
+        for root in roots {
+            out.push_str(root);
+            out.push('\n');
+            self.write_tree(root, "", &mut out);
+        }
+        out
+    }
+
+    fn write_tree(&self, name: &str, prefix: &str, out: &mut String) {
+        let deps = self.dependencies_of(name);
+        for (i, dep) in deps.iter().enumerate() {
+            let last = i == deps.len() - 1;
+            let (branch, indent) = if last {
+                ("└── ", "    ")
+            } else {
+                ("├── ", "│   ")
+            };
+            out.push_str(&format!("{prefix}{branch}{dep}\n"));
+            self.write_tree(dep, &format!("{prefix}{indent}"), out);
+        }
+    }
+
+    pub fn dot(&self, roots: &[&str]) -> String {
+        let mut out = String::from("digraph worker {\n");
+        for name in self.layers(roots).into_iter().flatten() {
+            let deps = self.dependencies_of(&name);
+            if deps.is_empty() {
+                out.push_str(&format!("    \"{name}\";\n"));
+            }
+            for dep in deps {
+                out.push_str(&format!("    \"{name}\" -> \"{dep}\";\n"));
+            }
+        }
+        out.push_str("}\n");
+        out
+    }
+}
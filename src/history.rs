@@ -0,0 +1,70 @@
+use std::{
+    os::unix::process::ExitStatusExt,
+    process::ExitStatus,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{format::format_ago, libc::Signal};
+
+/// A single run of a project, recorded by the supervisor when the command exits
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct Run {
+    pub pid: u32,
+    /// Seconds since the unix epoch
+    pub started_at: u64,
+    /// Seconds since the unix epoch
+    pub ended_at: u64,
+    pub code: Option<i32>,
+    pub signal: Option<i32>,
+    /// Whether the run ended because the project was stopped by worker
+    pub stopped: bool,
+    /// Number of times the project has crashed since it was started, including this run
+    pub crashes: u32,
+}
+
+impl Run {
+    pub fn new(
+        pid: u32,
+        started_at: SystemTime,
+        status: &ExitStatus,
+        stopped: bool,
+        crashes: u32,
+    ) -> Self {
+        Self {
+            pid,
+            started_at: unix_secs(started_at),
+            ended_at: unix_secs(SystemTime::now()),
+            code: status.code(),
+            signal: status.signal(),
+            stopped,
+            crashes,
+        }
+    }
+
+    /// How the run ended, e.g. `exited (code 101)` or `killed by SIGSEGV`
+    pub fn outcome(&self) -> String {
+        match (self.stopped, self.code, self.signal) {
+            (true, _, _) => "stopped".to_string(),
+            (_, Some(code), _) => format!("exited (code {code})"),
+            (_, _, Some(signal)) => match Signal::try_from(signal) {
+                Ok(signal) => format!("killed by {signal:?}"),
+                Err(_) => format!("killed by signal {signal}"),
+            },
+            _ => "exited".to_string(),
+        }
+    }
+}
+
+impl std::fmt::Display for Run {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {}", self.outcome(), format_ago(self.ended_at))
+    }
+}
+
+pub fn unix_secs(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
@@ -1,5 +1,20 @@
+use std::{
+    collections::HashMap,
+    ffi::CString,
+    os::{
+        fd::{AsRawFd, FromRawFd, OwnedFd, RawFd},
+        unix::ffi::OsStrExt,
+    },
+    path::Path,
+    str::FromStr,
+};
+
 use serde::{Deserialize, Serialize};
-use sysinfo::System;
+use sysinfo::{Process, ProcessStatus, ProcessesToUpdate, System, MINIMUM_CPU_UPDATE_INTERVAL};
+
+// Size of the terminal the projects with `pty` write to
+const PTY_ROWS: u16 = 24;
+const PTY_COLUMNS: u16 = 80;
 
 pub enum Fork {
     Parent(libc::pid_t),
@@ -40,13 +55,170 @@ pub fn stop_pg(sid: i32, signal: &Signal) -> Result<(), i32> {
     }
 }
 
+pub fn handle_signal(signal: &Signal, handler: extern "C" fn(libc::c_int)) -> Result<(), i32> {
+    let mut action: libc::sigaction = unsafe { std::mem::zeroed() };
+    action.sa_sigaction = handler as libc::sighandler_t;
+    action.sa_flags = libc::SA_RESTART;
+
+    match unsafe { libc::sigaction(signal.to_owned() as i32, &action, std::ptr::null_mut()) } {
+        0 => Ok(()),
+        e => Err(e),
+    }
+}
+
+// Point stdin, stdout and stderr to /dev/null so a detached process doesn't keep the terminal open
+pub fn detach_stdio() -> Result<(), i32> {
+    let null = unsafe { libc::open(c"/dev/null".as_ptr(), libc::O_RDWR) };
+    if null == -1 {
+        return Err(-1);
+    }
+
+    for fd in [libc::STDIN_FILENO, libc::STDOUT_FILENO, libc::STDERR_FILENO] {
+        if unsafe { libc::dup2(null, fd) } == -1 {
+            return Err(-1);
+        }
+    }
+
+    unsafe { libc::close(null) };
+
+    Ok(())
+}
+
+// Opens a pseudo-terminal and returns the master and slave side. The slave doesn't translate `\n`
+// to `\r\n`, so the output reads like it was written to a file, and doesn't echo the input
+pub fn openpty() -> Result<(OwnedFd, OwnedFd), i32> {
+    let mut master = -1;
+    let mut slave = -1;
+    let size = libc::winsize {
+        ws_row: PTY_ROWS,
+        ws_col: PTY_COLUMNS,
+        ws_xpixel: 0,
+        ws_ypixel: 0,
+    };
+
+    let res = unsafe {
+        libc::openpty(
+            &mut master,
+            &mut slave,
+            std::ptr::null_mut(),
+            std::ptr::null(),
+            &size,
+        )
+    };
+    if res == -1 {
+        return Err(-1);
+    }
+    let (master, slave) = unsafe { (OwnedFd::from_raw_fd(master), OwnedFd::from_raw_fd(slave)) };
+
+    let mut termios: libc::termios = unsafe { std::mem::zeroed() };
+    if unsafe { libc::tcgetattr(slave.as_raw_fd(), &mut termios) } == -1 {
+        return Err(-1);
+    }
+    termios.c_oflag &= !libc::ONLCR;
+    // Input from `worker attach` is echoed by the terminal it is typed in
+    termios.c_lflag &= !libc::ECHO;
+    if unsafe { libc::tcsetattr(slave.as_raw_fd(), libc::TCSANOW, &termios) } == -1 {
+        return Err(-1);
+    }
+
+    Ok((master, slave))
+}
+
+pub fn mkfifo(path: &Path) -> Result<(), i32> {
+    let path = CString::new(path.as_os_str().as_bytes()).map_err(|_| -1)?;
+    match unsafe { libc::mkfifo(path.as_ptr(), 0o600) } {
+        0 => Ok(()),
+        e => Err(e),
+    }
+}
+
+/// Lets the terminal hand over every key as soon as it is typed, including the signal characters,
+/// instead of a line at a time. What is typed is still echoed, and Enter still ends a line with a
+/// newline. The previous mode is restored when dropped
+pub struct RawMode {
+    fd: RawFd,
+    original: libc::termios,
+}
+
+impl RawMode {
+    pub fn enable(fd: RawFd) -> Result<Self, i32> {
+        let mut original: libc::termios = unsafe { std::mem::zeroed() };
+        if unsafe { libc::tcgetattr(fd, &mut original) } == -1 {
+            return Err(-1);
+        }
+
+        let mut termios = original;
+        termios.c_lflag &= !(libc::ICANON | libc::ISIG | libc::IEXTEN);
+        termios.c_iflag &= !libc::IXON;
+        termios.c_cc[libc::VMIN] = 1;
+        termios.c_cc[libc::VTIME] = 0;
+        if unsafe { libc::tcsetattr(fd, libc::TCSANOW, &termios) } == -1 {
+            return Err(-1);
+        }
+
+        Ok(Self { fd, original })
+    }
+}
+
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        unsafe { libc::tcsetattr(self.fd, libc::TCSANOW, &self.original) };
+    }
+}
+
+fn session_processes(sys: &System, sid: libc::pid_t) -> impl Iterator<Item = &Process> {
+    sys.processes().values().filter(move |p| {
+        // Exited processes waiting to be reaped by init are not running
+        p.status() != ProcessStatus::Zombie
+            && p.session_id()
+                .is_some_and(|session_id| session_id.as_u32() == sid as u32)
+    })
+}
+
 pub fn has_processes_running(sid: libc::pid_t) -> bool {
     let mut sys = System::new();
     sys.refresh_all();
-    sys.processes().iter().any(|(_, p)| {
-        p.session_id()
-            .is_some_and(|session_id| session_id.as_u32() == sid as u32)
-    })
+    let running = session_processes(&sys, sid).next().is_some();
+    running
+}
+
+/// Resources used by all processes in a session
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SessionUsage {
+    /// Sum of the CPU usage of the processes, where 100 is one core
+    pub cpu: f32,
+    /// Resident memory in bytes
+    pub memory: u64,
+    pub processes: usize,
+    /// When the oldest process was started, in seconds since the unix epoch
+    pub started_at: u64,
+}
+
+// Measuring CPU usage needs two samples, so this blocks for `MINIMUM_CPU_UPDATE_INTERVAL`
+pub fn session_usage(sids: &[libc::pid_t]) -> HashMap<libc::pid_t, SessionUsage> {
+    let mut sys = System::new();
+    sys.refresh_processes(ProcessesToUpdate::All, true);
+    std::thread::sleep(MINIMUM_CPU_UPDATE_INTERVAL);
+    sys.refresh_processes(ProcessesToUpdate::All, true);
+
+    sids.iter()
+        .filter_map(|&sid| {
+            let usage =
+                session_processes(&sys, sid).fold(None, |acc: Option<SessionUsage>, p| {
+                    let acc = acc.unwrap_or(SessionUsage {
+                        started_at: p.start_time(),
+                        ..Default::default()
+                    });
+                    Some(SessionUsage {
+                        cpu: acc.cpu + p.cpu_usage(),
+                        memory: acc.memory + p.memory(),
+                        processes: acc.processes + 1,
+                        started_at: acc.started_at.min(p.start_time()),
+                    })
+                })?;
+            Some((sid, usage))
+        })
+        .collect()
 }
 
 #[derive(Deserialize, Clone, Debug, Serialize, Hash, PartialEq, Eq)]
@@ -85,3 +257,68 @@ pub enum Signal {
     SIGPWR = 30,
     SIGSYS = 31,
 }
+
+impl Signal {
+    pub const ALL: [Signal; 31] = [
+        Signal::SIGHUP,
+        Signal::SIGINT,
+        Signal::SIGQUIT,
+        Signal::SIGILL,
+        Signal::SIGTRAP,
+        Signal::SIGABRT,
+        Signal::SIGBUS,
+        Signal::SIGFPE,
+        Signal::SIGKILL,
+        Signal::SIGUSR1,
+        Signal::SIGSEGV,
+        Signal::SIGUSR2,
+        Signal::SIGPIPE,
+        Signal::SIGALRM,
+        Signal::SIGTERM,
+        Signal::SIGSTKFLT,
+        Signal::SIGCHLD,
+        Signal::SIGCONT,
+        Signal::SIGSTOP,
+        Signal::SIGTSTP,
+        Signal::SIGTTIN,
+        Signal::SIGTTOU,
+        Signal::SIGURG,
+        Signal::SIGXCPU,
+        Signal::SIGXFSZ,
+        Signal::SIGVTALRM,
+        Signal::SIGPROF,
+        Signal::SIGWINCH,
+        Signal::SIGIO,
+        Signal::SIGPWR,
+        Signal::SIGSYS,
+    ];
+}
+
+/// Parses a signal by name, with or without the `SIG` prefix and in any case, or by number
+impl FromStr for Signal {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Ok(number) = s.parse::<i32>() {
+            return Signal::try_from(number).map_err(|_| format!("Unknown signal {s}"));
+        }
+
+        let name = s.to_uppercase();
+        let name = name.strip_prefix("SIG").unwrap_or(&name);
+        Signal::ALL
+            .into_iter()
+            .find(|signal| format!("{signal:?}")[3..] == *name)
+            .ok_or_else(|| format!("Unknown signal {s}"))
+    }
+}
+
+impl TryFrom<i32> for Signal {
+    type Error = i32;
+
+    fn try_from(value: i32) -> Result<Self, Self::Error> {
+        Signal::ALL
+            .into_iter()
+            .find(|signal| signal.to_owned() as i32 == value)
+            .ok_or(value)
+    }
+}
@@ -0,0 +1,544 @@
+use std::{
+    collections::VecDeque,
+    fs::{File, OpenOptions},
+    io::{ErrorKind, Read, Seek, SeekFrom, Write},
+    os::unix::fs::MetadataExt,
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::SystemTime,
+};
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+const DEFAULT_KEEP: usize = 1;
+
+// Size of the blocks the log file is read in
+const READ_CHUNK: usize = 8192;
+
+// Length of a timestamp formatted with millisecond precision, e.g. `2024-04-20T12:34:56.789Z`
+const TIMESTAMP_LEN: usize = 24;
+
+/// Log settings deserialized from the `log` block of a project
+#[derive(Deserialize, Serialize, Clone, Debug, Eq, PartialEq, Default)]
+pub struct LogConfig {
+    /// Rotate the log file when it grows larger than this
+    pub max_size: Option<ByteSize>,
+    /// Number of previous log files to keep, e.g. `backend.1` and `backend.2`
+    pub keep: Option<usize>,
+    /// Prefix each line of the log file with the time it was written
+    pub timestamps: Option<bool>,
+}
+
+/// Size in bytes. Deserialized from either a number, or a string with a `K`, `M` or `G` suffix
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, Eq, PartialEq)]
+#[serde(try_from = "SizeValue", into = "u64")]
+pub struct ByteSize(pub u64);
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum SizeValue {
+    Bytes(u64),
+    Text(String),
+}
+
+impl TryFrom<SizeValue> for ByteSize {
+    type Error = String;
+
+    fn try_from(value: SizeValue) -> Result<Self, Self::Error> {
+        let text = match value {
+            SizeValue::Bytes(bytes) => return Ok(ByteSize(bytes)),
+            SizeValue::Text(text) => text,
+        };
+
+        let trimmed = text.trim().trim_end_matches(['B', 'b']);
+        let (number, multiplier) = match trimmed.char_indices().last() {
+            Some((i, 'K' | 'k')) => (&trimmed[..i], 1 << 10),
+            Some((i, 'M' | 'm')) => (&trimmed[..i], 1 << 20),
+            Some((i, 'G' | 'g')) => (&trimmed[..i], 1 << 30),
+            _ => (trimmed, 1),
+        };
+
+        number
+            .trim()
+            .parse::<u64>()
+            .map(|n| ByteSize(n * multiplier))
+            .map_err(|_| format!("Invalid size {text}, expected e.g. 512K or 10M"))
+    }
+}
+
+impl From<ByteSize> for u64 {
+    fn from(value: ByteSize) -> Self {
+        value.0
+    }
+}
+
+/// Writes the output of a project to its log file, prefixing every line with the stream it was
+/// written to and optionally the time, and rotating the file when it gets too large
+pub struct LogWriter {
+    path: PathBuf,
+    file: File,
+    timestamps: bool,
+    size: u64,
+    max_size: Option<u64>,
+    keep: usize,
+    // The stream of the last line, if it hasn't ended yet
+    open_line: Option<Stream>,
+}
+
+/// Which output stream of the project a line was written to
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Stream {
+    Stdout,
+    Stderr,
+}
+
+impl Stream {
+    fn tag(&self) -> &'static str {
+        match self {
+            Stream::Stdout => "out",
+            Stream::Stderr => "err",
+        }
+    }
+}
+
+impl LogWriter {
+    /// Rotates away the log file of the previous run. Done before the project is started, so
+    /// nothing in the log file is left from the previous run once `worker start` returns
+    pub fn rotate(path: &Path, config: &LogConfig) -> Result<(), std::io::Error> {
+        rotate(path, config.keep.unwrap_or(DEFAULT_KEEP))
+    }
+
+    /// Appends to the log file of the current run
+    pub fn open(path: PathBuf, config: &LogConfig) -> Result<Self, std::io::Error> {
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+
+        Ok(Self {
+            size: file.metadata()?.len(),
+            file,
+            path,
+            max_size: config.max_size.map(u64::from),
+            keep: config.keep.unwrap_or(DEFAULT_KEEP),
+            timestamps: config.timestamps.unwrap_or_default(),
+            open_line: None,
+        })
+    }
+
+    pub fn write(&mut self, stream: Stream, data: &[u8]) -> Result<(), std::io::Error> {
+        // Don't mix output from the other stream into an unfinished line
+        if self.open_line.is_some_and(|open| open != stream) {
+            self.file.write_all(b"\n")?;
+            self.size += 1;
+            self.open_line = None;
+        }
+
+        for line in data.split_inclusive(|b| *b == b'\n') {
+            if self.open_line.is_none() {
+                let prefix = if self.timestamps {
+                    let timestamp = humantime::format_rfc3339_millis(SystemTime::now());
+                    format!("{timestamp} {} ", stream.tag())
+                } else {
+                    format!("{} ", stream.tag())
+                };
+                self.file.write_all(prefix.as_bytes())?;
+                self.size += prefix.len() as u64;
+            }
+
+            self.file.write_all(line)?;
+            self.size += line.len() as u64;
+            self.open_line = (!line.ends_with(b"\n")).then_some(stream);
+
+            // Only rotate between lines, so a line is never split across two files
+            if self.open_line.is_none() && self.max_size.is_some_and(|max| self.size >= max) {
+                rotate(&self.path, self.keep)?;
+                self.file = File::create(&self.path)?;
+                self.size = 0;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Copy everything from the reader to the log file until it is closed
+pub fn copy(
+    log: &Mutex<LogWriter>,
+    stream: Stream,
+    mut reader: impl Read,
+) -> Result<(), std::io::Error> {
+    let mut buf = [0; 8192];
+    loop {
+        match reader.read(&mut buf) {
+            Ok(0) => return Ok(()),
+            Ok(n) => log
+                .lock()
+                .expect("Log writer lock poisoned")
+                .write(stream, &buf[..n])?,
+            Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+            // The master of a pseudo-terminal fails instead of ending once nothing has it open
+            Err(e) if e.raw_os_error() == Some(libc::EIO) => return Ok(()),
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+// Shift `name.1` to `name.2` and so on, dropping the oldest, and move `name` to `name.1`
+fn rotate(path: &Path, keep: usize) -> Result<(), std::io::Error> {
+    let rotated = |n: usize| {
+        let mut name = path.as_os_str().to_owned();
+        name.push(format!(".{n}"));
+        PathBuf::from(name)
+    };
+
+    let result = if keep == 0 {
+        std::fs::remove_file(path)
+    } else {
+        let _ = std::fs::remove_file(rotated(keep));
+        for n in (1..keep).rev() {
+            let _ = std::fs::rename(rotated(n), rotated(n + 1));
+        }
+        std::fs::rename(path, rotated(1))
+    };
+
+    match result {
+        Err(e) if e.kind() != ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
+
+/// A line from the log file, split into the time it was written, the stream it was written to
+/// and the output of the project
+pub struct LogLine<'a> {
+    pub timestamp: Option<&'a str>,
+    pub stream: Option<Stream>,
+    pub content: &'a str,
+}
+
+impl<'a> LogLine<'a> {
+    /// Splits off the prefix worker writes in front of the line. The time is only looked for when
+    /// the project logs `timestamps`, so output that starts with a time of its own is kept as is
+    pub fn parse(line: &'a str, timestamps: bool) -> Self {
+        let (timestamp, rest) = match line.split_at_checked(TIMESTAMP_LEN) {
+            Some((timestamp, rest))
+                if timestamps
+                    && rest.starts_with(' ')
+                    && humantime::parse_rfc3339(timestamp).is_ok() =>
+            {
+                (Some(timestamp), &rest[1..])
+            }
+            _ => (None, line),
+        };
+
+        let (stream, content) = match rest.split_at_checked(4) {
+            Some(("out ", content)) => (Some(Stream::Stdout), content),
+            Some(("err ", content)) => (Some(Stream::Stderr), content),
+            _ => (None, rest),
+        };
+
+        Self {
+            timestamp,
+            stream,
+            content,
+        }
+    }
+
+    /// When the line was written, to the millisecond
+    pub fn written(&self) -> Option<SystemTime> {
+        humantime::parse_rfc3339(self.timestamp?).ok()
+    }
+
+    pub fn format(&self, timestamps: bool) -> String {
+        match self.timestamp {
+            Some(timestamp) if timestamps => format!("{timestamp} {}", self.content),
+            _ => self.content.to_string(),
+        }
+    }
+}
+
+/// Decides which lines of the log are printed by `worker logs`
+#[derive(Deserialize, Serialize, Clone, Debug, Default)]
+pub struct LogFilter {
+    pub stream: Option<Stream>,
+    #[serde(with = "regex_pattern")]
+    pub grep: Option<Regex>,
+    /// Only keep the lines not matching `grep`
+    pub invert: bool,
+    pub since: Option<SystemTime>,
+    pub until: Option<SystemTime>,
+}
+
+impl LogFilter {
+    pub fn matches(&self, line: &LogLine) -> bool {
+        if self.stream.is_some() && line.stream != self.stream {
+            return false;
+        }
+
+        if let Some(ref grep) = self.grep {
+            if grep.is_match(line.content) == self.invert {
+                return false;
+            }
+        }
+
+        if self.since.is_none() && self.until.is_none() {
+            return true;
+        }
+
+        // Lines without a timestamp can't be placed in time, so they never match a time range
+        let Some(written) = line.written() else {
+            return false;
+        };
+
+        self.since.is_none_or(|since| written >= since)
+            && self.until.is_none_or(|until| written <= until)
+    }
+}
+
+// Sends the regex of a filter to the daemon as its pattern
+mod regex_pattern {
+    use regex::Regex;
+    use serde::{de::Error, Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S: Serializer>(
+        grep: &Option<Regex>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        grep.as_ref().map(Regex::as_str).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<Regex>, D::Error> {
+        Option::<String>::deserialize(deserializer)?
+            .map(|pattern| Regex::new(&pattern).map_err(D::Error::custom))
+            .transpose()
+    }
+}
+
+/// Parses a point in time given either as a duration ago, e.g. `10m` or `1h 30m`, or as a UTC
+/// timestamp, e.g. `2024-04-20 12:00:00` or `2024-04-20T12:00:00Z`
+pub fn parse_time(s: &str) -> Result<SystemTime, String> {
+    if let Ok(duration) = humantime::parse_duration(s) {
+        return SystemTime::now()
+            .checked_sub(duration)
+            .ok_or_else(|| format!("{s} is too long ago"));
+    }
+
+    humantime::parse_rfc3339_weak(s)
+        .map_err(|_| format!("{s} is neither a duration like `10m` nor a timestamp"))
+}
+
+/// Reads a log file from where it last stopped, following it across truncation and rotation
+pub struct LogReader {
+    path: PathBuf,
+    file: Option<File>,
+    pos: u64,
+    partial: Vec<u8>,
+}
+
+impl LogReader {
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            file: None,
+            pos: 0,
+            partial: vec![],
+        }
+    }
+
+    /// Calls `on_line` for every complete line written since the last call
+    pub fn read_lines(&mut self, mut on_line: impl FnMut(&str)) -> Result<(), std::io::Error> {
+        let current = match std::fs::metadata(&self.path) {
+            Ok(metadata) => Some(metadata),
+            Err(e) if e.kind() == ErrorKind::NotFound => None,
+            Err(e) => return Err(e),
+        };
+
+        if let Some(ref file) = self.file {
+            let rotated = current
+                .as_ref()
+                .is_none_or(|current| current.ino() != file.metadata().map_or(0, |m| m.ino()));
+
+            if rotated {
+                // Finish reading the old file before moving on to the new one
+                self.read_to_end(&mut on_line)?;
+                if let Some(line) = self.take_partial() {
+                    on_line(&line);
+                }
+                self.file = None;
+            } else if current
+                .as_ref()
+                .is_some_and(|current| current.len() < self.pos)
+            {
+                // Truncated, so start from the beginning again
+                self.pos = 0;
+                self.partial.clear();
+            }
+        }
+
+        if self.file.is_none() && current.is_some() {
+            self.file = Some(File::open(&self.path)?);
+            self.pos = 0;
+        }
+
+        self.read_to_end(&mut on_line)
+    }
+
+    /// Skips ahead to the last `number` lines for which `wanted` holds, so the next
+    /// `read_lines` starts there instead of at the beginning of the file
+    pub fn tail(
+        &mut self,
+        number: usize,
+        wanted: impl Fn(&str) -> bool,
+    ) -> Result<(), std::io::Error> {
+        let Some(mut file) = self.open()? else {
+            return Ok(());
+        };
+
+        self.pos = tail_start(&mut file, number, wanted)?;
+        self.file = Some(file);
+        self.partial.clear();
+
+        Ok(())
+    }
+
+    /// The last `number` lines for which `wanted` holds, after which the reader goes on with the
+    /// lines written later
+    pub fn last_lines(
+        &mut self,
+        number: usize,
+        wanted: impl Fn(&str) -> bool,
+    ) -> Result<Vec<String>, std::io::Error> {
+        self.tail(number, &wanted)?;
+
+        let mut lines = VecDeque::with_capacity(number);
+        self.read_lines(|line| {
+            if wanted(line) {
+                if lines.len() == number {
+                    lines.pop_front();
+                }
+                if number > 0 {
+                    lines.push_back(line.to_string());
+                }
+            }
+        })?;
+
+        Ok(lines.into())
+    }
+
+    /// Where the next complete line starts, to continue from with `seek`
+    pub fn position(&self) -> u64 {
+        self.pos - self.partial.len() as u64
+    }
+
+    /// Continues reading at `pos`, e.g. where the daemon stopped reading the log
+    pub fn seek(&mut self, pos: u64) -> Result<(), std::io::Error> {
+        self.file = self.open()?;
+        self.pos = pos;
+        self.partial.clear();
+
+        Ok(())
+    }
+
+    fn open(&self) -> Result<Option<File>, std::io::Error> {
+        match File::open(&self.path) {
+            Ok(file) => Ok(Some(file)),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn read_to_end(&mut self, on_line: &mut impl FnMut(&str)) -> Result<(), std::io::Error> {
+        if let Some(ref mut file) = self.file {
+            file.seek(SeekFrom::Start(self.pos))?;
+        }
+
+        let mut buf = [0; READ_CHUNK];
+        loop {
+            let Some(ref mut file) = self.file else {
+                return Ok(());
+            };
+            let n = match file.read(&mut buf) {
+                Ok(0) => return Ok(()),
+                Ok(n) => n,
+                Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            };
+            self.pos += n as u64;
+
+            for chunk in buf[..n].split_inclusive(|b| *b == b'\n') {
+                self.partial.extend_from_slice(chunk);
+                if chunk.ends_with(b"\n") {
+                    if let Some(line) = self.take_partial() {
+                        on_line(&line);
+                    }
+                }
+            }
+        }
+    }
+
+    fn take_partial(&mut self) -> Option<String> {
+        if self.partial.is_empty() {
+            return None;
+        }
+
+        let line = String::from_utf8_lossy(&self.partial)
+            .trim_end_matches('\n')
+            .to_string();
+        self.partial.clear();
+        Some(line)
+    }
+}
+
+// Finds where the last `number` complete lines for which `wanted` holds start, by reading back
+// from the end of the file a block at a time
+fn tail_start(
+    file: &mut File,
+    number: usize,
+    wanted: impl Fn(&str) -> bool,
+) -> Result<u64, std::io::Error> {
+    let mut pos = file.metadata()?.len();
+    if number == 0 {
+        return Ok(pos);
+    }
+
+    let mut found = 0;
+    // The start of the line the previous block began in the middle of
+    let mut rest = vec![];
+    while pos > 0 {
+        let size = pos.min(READ_CHUNK as u64);
+        pos -= size;
+
+        let mut block = vec![0; size as usize];
+        file.seek(SeekFrom::Start(pos))?;
+        file.read_exact(&mut block)?;
+        block.extend_from_slice(&rest);
+
+        let mut lines = vec![];
+        let mut start = 0;
+        for line in block.split_inclusive(|b| *b == b'\n') {
+            lines.push((start, line));
+            start += line.len();
+        }
+
+        // Unless this is the start of the file, the first line may continue in the block before
+        let complete = if pos == 0 { 0 } else { 1 };
+        for &(start, line) in lines.iter().skip(complete).rev() {
+            let Some(line) = line.strip_suffix(b"\n") else {
+                continue;
+            };
+            if wanted(&String::from_utf8_lossy(line)) {
+                found += 1;
+                if found == number {
+                    return Ok(pos + start as u64);
+                }
+            }
+        }
+
+        rest = lines
+            .first()
+            .map(|(_, line)| line.to_vec())
+            .unwrap_or_default();
+    }
+
+    Ok(0)
+}
@@ -1,15 +1,48 @@
-use std::time::{Duration, Instant};
-
-use clap::{command, ArgGroup, Parser};
+use std::{
+    collections::{HashMap, HashSet},
+    io::IsTerminal,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::anyhow;
+use clap::{ArgGroup, Parser};
 use config::WorkerConfig;
+use format::{format_duration, print_json, print_table, Format, ProjectInfo};
 use itertools::Itertools;
 use project::Project;
-
-use crate::project::RunningProject;
-
+use regex::Regex;
+use serde::Serialize;
+
+use crate::{
+    daemon::Client,
+    libc::{session_usage, Signal},
+    log::{parse_time, LogFilter, LogLine, LogReader, Stream},
+    project::{RunningProject, WorkerProject},
+};
+
+pub mod attach;
+pub mod command;
 pub mod config;
+pub mod daemon;
+pub mod env;
+pub mod format;
+pub mod graph;
+pub mod history;
 pub mod libc;
+pub mod log;
 pub mod project;
+pub mod ready;
+pub mod ui;
+pub mod validate;
+pub mod watch;
+
+// How long to wait for a project to exit after it has been sent the kill signal
+const KILL_TIMEOUT: Duration = Duration::from_secs(5);
+
+const FOLLOW_POLL_INTERVAL: Duration = Duration::from_millis(250);
+const FOLLOW_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+// How long `logs --follow` waits for a stopped project to be started again, e.g. by a restart
+const FOLLOW_GRACE: Duration = Duration::from_secs(5);
 
 fn start(config: &WorkerConfig, projects: Vec<Project>) -> Result<(), anyhow::Error> {
     let (running, not_running) = config.partition_projects(projects)?;
@@ -18,57 +51,218 @@ fn start(config: &WorkerConfig, projects: Vec<Project>) -> Result<(), anyhow::Er
         eprintln!("{} is already running", project);
     }
 
-    for project in not_running {
-        project.start(config)?;
+    start_with_dependencies(config, &not_running, false)
+}
+
+// Start the projects and their dependencies in topological order. Dependencies within the same
+// layer are waited on in parallel, before moving on to the projects depending on them
+fn start_with_dependencies(
+    config: &WorkerConfig,
+    projects: &[Project],
+    wait_until_ready: bool,
+) -> Result<(), anyhow::Error> {
+    let layers = config.start_order(projects);
+
+    let mut needs_ready: HashSet<_> = layers
+        .iter()
+        .flatten()
+        .flat_map(|p| config.graph.dependencies_of(&p.name))
+        .cloned()
+        .collect();
+
+    if wait_until_ready {
+        needs_ready.extend(projects.iter().map(|p| p.name.clone()));
+    }
+
+    for layer in layers {
+        let (_, not_running) = config.partition_projects(layer.clone())?;
+        let started_at = SystemTime::now();
+        let started: HashSet<_> = not_running.iter().map(|p| p.name.clone()).collect();
+        for project in not_running {
+            project.start(config)?;
+        }
+
+        std::thread::scope(|s| {
+            layer
+                .iter()
+                .filter(|p| needs_ready.contains(&p.name))
+                .map(|p| {
+                    let started_at = started.contains(&p.name).then_some(started_at);
+                    s.spawn(move || p.wait_until_ready(config, started_at))
+                })
+                .collect::<Vec<_>>()
+                .into_iter()
+                .try_for_each(|handle| handle.join().expect("Readiness check panicked"))
+        })?;
     }
 
     Ok(())
 }
 
-fn stop(config: &WorkerConfig, projects: Vec<RunningProject>) -> Result<(), anyhow::Error> {
+fn stop(
+    config: &WorkerConfig,
+    projects: Vec<RunningProject>,
+    force: bool,
+) -> Result<(), anyhow::Error> {
+    let start = Instant::now();
+
+    // Projects that have been sent the kill signal, and when
+    let mut killed = HashMap::new();
+    let started: HashMap<_, _> = projects
+        .iter()
+        .filter_map(|p| Some((p.name.clone(), config.started_at(p)?)))
+        .collect();
+
     for project in projects.iter() {
-        project.stop()?;
+        if force {
+            project.kill(config)?;
+            killed.insert(project.name.clone(), (start, SystemTime::now()));
+        } else {
+            project.stop(config)?;
+        }
     }
 
-    let timeout = Duration::new(5, 0);
-    let start = Instant::now();
-
-    while Instant::now().duration_since(start) < timeout {
+    let mut projects = projects;
+    loop {
         let (still_running, _) = config.partition_projects(projects.clone())?;
+
+        // The kill signal reaches the supervisor as well, which then doesn't record the run
+        for p in projects
+            .iter()
+            .filter(|p| !still_running.iter().any(|r| r.name == p.name))
+        {
+            if let (Some((_, killed_at)), Some(started_at)) =
+                (killed.get(&p.name), started.get(&p.name))
+            {
+                config.record_killed(p, *started_at, *killed_at)?;
+            }
+        }
+
         if still_running.is_empty() {
             return Ok(());
         }
+
+        let now = Instant::now();
+        let (given_up, still_running): (Vec<_>, Vec<_>) =
+            still_running.into_iter().partition(|p| {
+                killed
+                    .get(&p.name)
+                    .is_some_and(|&(at, _)| now.duration_since(at) >= KILL_TIMEOUT)
+            });
+
+        for p in given_up {
+            eprintln!("Was not able to stop {}", p);
+        }
+
+        for p in still_running.iter() {
+            if !killed.contains_key(&p.name) && now.duration_since(start) >= p.stop_timeout() {
+                eprintln!("{} did not stop in time, killing it", p);
+                p.kill(config)?;
+                killed.insert(p.name.clone(), (now, SystemTime::now()));
+            }
+        }
+
+        projects = still_running;
+        std::thread::sleep(Duration::from_millis(100));
     }
+}
 
-    let (still_running, _) = config.partition_projects(projects)?;
-    for p in still_running {
-        eprintln!("Was not able to stop {}", p);
+// Stop the projects after everything depending on them has been stopped
+fn stop_cascade(
+    config: &WorkerConfig,
+    projects: Vec<RunningProject>,
+    force: bool,
+) -> Result<Vec<RunningProject>, anyhow::Error> {
+    let layers = config.stop_order(&projects)?;
+    for layer in layers.iter() {
+        stop(config, layer.clone(), force)?;
     }
 
-    Ok(())
+    Ok(layers.into_iter().flatten().collect())
 }
 
-fn restart(config: &WorkerConfig, projects: Vec<RunningProject>) -> Result<(), anyhow::Error> {
-    stop(config, projects.clone())?;
+fn restart(
+    config: &WorkerConfig,
+    projects: Vec<RunningProject>,
+    cascade: bool,
+) -> Result<(), anyhow::Error> {
+    let projects = if cascade {
+        stop_cascade(config, projects, false)?
+    } else {
+        stop(config, projects.clone(), false)?;
+        projects
+    };
+
     start(config, projects.into_iter().map(|p| p.into()).collect())?;
 
     Ok(())
 }
 
 fn run(config: &WorkerConfig, project: Project) -> Result<(), anyhow::Error> {
-    project.start_dependencies(config)?;
-
-    project.run()?;
+    let dependencies: Vec<_> = config
+        .projects
+        .iter()
+        .filter(|p| {
+            config
+                .graph
+                .dependencies_of(&project.name)
+                .contains(&p.name)
+        })
+        .cloned()
+        .collect();
+
+    start_with_dependencies(config, &dependencies, true)?;
+
+    project.run(config)?;
 
     Ok(())
 }
 
 fn status(config: &WorkerConfig, args: StatusArgs) -> Result<(), anyhow::Error> {
-    for project in config.running()? {
-        if args.quiet {
-            println!("{}", project.name);
-        } else {
-            println!("{} is running", project);
+    let running = config.running()?;
+
+    // Projects from the config that are not running, and the last time they ran
+    let not_running = if args.all {
+        config
+            .projects
+            .iter()
+            .filter(|p| !running.iter().any(|it| it.name == p.name))
+            .map(|p| Ok((p.clone(), config.history(&p.name)?.pop())))
+            .collect::<Result<Vec<_>, anyhow::Error>>()?
+    } else {
+        vec![]
+    };
+
+    match args.format {
+        Format::Text => {
+            for project in running {
+                if args.quiet {
+                    println!("{}", project.name);
+                } else {
+                    println!("{} is running", project);
+                }
+            }
+
+            for (project, last_run) in not_running {
+                match last_run {
+                    _ if args.quiet => println!("{}", project.name),
+                    Some(run) => println!("{} {}", project, run),
+                    None => println!("{} is not running", project),
+                }
+            }
+        }
+        format => {
+            let usage = session_usage(&running.iter().map(|p| p.pid).collect::<Vec<_>>());
+            let projects: Vec<_> = running
+                .iter()
+                .map(|p| ProjectInfo::new(&p.clone().into(), Some(p), usage.get(&p.pid)))
+                .chain(not_running.into_iter().map(|(p, last_run)| {
+                    let mut info = ProjectInfo::new(&p, None, None);
+                    info.last_run = last_run;
+                    info
+                }))
+                .collect();
+            print_projects(&projects, format)?;
         }
     }
 
@@ -76,50 +270,300 @@ fn status(config: &WorkerConfig, args: StatusArgs) -> Result<(), anyhow::Error>
 }
 
 fn list(config: &WorkerConfig, args: ListArgs) -> Result<(), anyhow::Error> {
-    for p in config.projects.iter() {
-        if args.quiet {
-            println!("{}", p.name)
-        } else {
-            println!("{}", p)
+    match args.format {
+        Format::Text => {
+            for p in config.projects.iter() {
+                if args.quiet {
+                    println!("{}", p.name)
+                } else {
+                    println!("{}", p)
+                }
+            }
+        }
+        format => {
+            let running = config.running()?;
+            let usage = session_usage(&running.iter().map(|p| p.pid).collect::<Vec<_>>());
+            let projects: Vec<_> = config
+                .projects
+                .iter()
+                .map(|p| {
+                    let running = running.iter().find(|it| it.name == p.name);
+                    ProjectInfo::new(p, running, running.and_then(|r| usage.get(&r.pid)))
+                })
+                .collect();
+            print_projects(&projects, format)?;
         }
     }
 
     Ok(())
 }
 
-fn logs(config: &WorkerConfig, args: LogsArgs) -> Result<(), anyhow::Error> {
-    let mut cmd = std::process::Command::new("tail");
+fn print_projects(projects: &[ProjectInfo], format: Format) -> Result<(), anyhow::Error> {
+    match format {
+        Format::Json => print_json(projects)?,
+        Format::Table => print_table(projects),
+        Format::Text => unreachable!("Text is printed by the command itself"),
+    }
+
+    Ok(())
+}
+
+fn graph(config: &WorkerConfig, args: GraphArgs) -> Result<(), anyhow::Error> {
+    let projects: Vec<_> = args
+        .projects
+        .into_iter()
+        .flat_map(|it| match it {
+            ActionArg::Project(project) => vec![*project],
+            ActionArg::Group(vec) => vec,
+        })
+        .unique()
+        .collect();
+
+    let roots = if projects.is_empty() {
+        config.graph.roots()
+    } else {
+        projects.iter().map(|p| p.name.as_str()).collect()
+    };
+
+    if args.dot {
+        print!("{}", config.graph.dot(&roots));
+    } else {
+        print!("{}", config.graph.tree(&roots));
+    }
+
+    Ok(())
+}
+
+fn reload(config: &WorkerConfig, projects: Vec<RunningProject>) -> Result<(), anyhow::Error> {
+    // Nothing is reloaded unless every project can be
+    if let Some(project) = projects.iter().find(|p| p.reload_signal.is_none()) {
+        return Err(anyhow!(
+            "{project} has no reload_signal, use `worker restart` instead"
+        ));
+    }
+
+    for project in projects {
+        project.reload(config)?;
+    }
+
+    Ok(())
+}
+
+fn history(config: &WorkerConfig, args: HistoryArgs) -> Result<(), anyhow::Error> {
+    let history = config.history(&args.project)?;
+    if history.is_empty() {
+        return Err(anyhow!("No recorded runs of {}", args.project));
+    }
 
-    if args.follow {
-        cmd.arg("-f");
+    println!(
+        "{:<20}  {:>8}  {:<20}  CRASHES",
+        "STARTED", "DURATION", "EXIT"
+    );
+
+    let skip = history.len().saturating_sub(args.number);
+    for run in history.into_iter().skip(skip) {
+        let started_at = UNIX_EPOCH + Duration::from_secs(run.started_at);
+        let duration = Duration::from_secs(run.ended_at.saturating_sub(run.started_at));
+        println!(
+            "{:<20}  {:>8}  {:<20}  {}",
+            humantime::format_rfc3339_seconds(started_at).to_string(),
+            format_duration(duration),
+            run.outcome(),
+            run.crashes,
+        );
     }
 
-    let mut child = cmd
-        .args(["-n", &args.number.to_string()])
-        .arg(config.log_file(&args.project))
-        .spawn()?;
+    Ok(())
+}
+
+// Colours used for the prefix of each project when printing logs of several projects
+const LOG_COLORS: [u8; 6] = [36, 33, 32, 35, 34, 31];
+
+/// The log of one project, together with the prefix to print in front of its lines
+struct ProjectLog {
+    project: RunningProject,
+    reader: LogReader,
+    prefix: String,
+    timestamps: bool,
+}
+
+impl ProjectLog {
+    fn parse<'a>(&self, line: &'a str) -> LogLine<'a> {
+        LogLine::parse(line, self.project.logs_timestamps())
+    }
+
+    fn print(&self, line: &str) {
+        println!(
+            "{}{}",
+            self.prefix,
+            self.parse(line).format(self.timestamps)
+        );
+    }
+}
+
+fn logs(
+    config: &WorkerConfig,
+    projects: Vec<RunningProject>,
+    args: LogsArgs,
+) -> Result<(), anyhow::Error> {
+    if projects.is_empty() {
+        return Err(anyhow!("No running projects to print logs for"));
+    }
+
+    // The time of each line is only known when it is written to the log file
+    let needs_timestamps = args.timestamps || args.since.is_some() || args.until.is_some();
+    if let Some(project) = projects
+        .iter()
+        .find(|p| needs_timestamps && !p.logs_timestamps())
+    {
+        return Err(anyhow!(
+            "{project} doesn't log timestamps. Set `log = {{ timestamps = true }}` on the project to print them or to filter by time"
+        ));
+    }
+
+    let filter = LogFilter {
+        stream: match (args.stdout, args.stderr) {
+            (true, false) => Some(Stream::Stdout),
+            (false, true) => Some(Stream::Stderr),
+            _ => None,
+        },
+        grep: args.grep,
+        invert: args.invert,
+        since: args.since,
+        until: args.until,
+    };
+    let wanted = |timestamps: bool, line: &str| filter.matches(&LogLine::parse(line, timestamps));
+
+    // Only prefix the lines with the name of the project when there is more than one
+    let width = projects.iter().map(|p| p.name.len()).max().unwrap_or(0);
+    let color = std::io::stdout().is_terminal() && std::env::var_os("NO_COLOR").is_none();
+    let multiple = projects.len() > 1;
+
+    let mut logs: Vec<_> = projects
+        .into_iter()
+        .enumerate()
+        .map(|(i, project)| {
+            let prefix = match (multiple, color) {
+                (false, _) => String::new(),
+                (true, false) => format!("{:<width$} | ", project.name),
+                (true, true) => format!(
+                    "\x1b[{}m{:<width$} |\x1b[0m ",
+                    LOG_COLORS[i % LOG_COLORS.len()],
+                    project.name
+                ),
+            };
+            ProjectLog {
+                reader: LogReader::new(config.log_file(&project)),
+                project,
+                prefix,
+                timestamps: args.timestamps,
+            }
+        })
+        .collect();
+
+    // The last lines of every project, interleaved in the order they were written
+    let mut last_lines = vec![];
+    for (i, log) in logs.iter_mut().enumerate() {
+        let timestamps = log.project.logs_timestamps();
+        // The daemon reads them when it runs, and the rest is followed from where it stopped
+        let lines = match Client::connect(config) {
+            Some(daemon) => {
+                let (lines, pos) = daemon.logs(&log.project.name, args.number, &filter)?;
+                log.reader.seek(pos)?;
+                lines
+            }
+            None => log
+                .reader
+                .last_lines(args.number, |line| wanted(timestamps, line))?,
+        };
+        last_lines.extend(lines.into_iter().map(|line| (i, line)));
+    }
+    print_interleaved(&logs, last_lines);
+
+    if !args.follow {
+        return Ok(());
+    }
 
-    if args.follow {
-        while args.project.is_running() {
-            std::thread::sleep(Duration::from_secs(2));
+    // Keep following across restarts, and only stop once every project has been stopped for a
+    // while
+    let mut last_running = Instant::now();
+    let mut last_checked = Instant::now();
+    loop {
+        let mut new_lines = vec![];
+        for (i, log) in logs.iter_mut().enumerate() {
+            let timestamps = log.project.logs_timestamps();
+            log.reader.read_lines(|line| {
+                if wanted(timestamps, line) {
+                    new_lines.push((i, line.to_string()));
+                }
+            })?;
         }
-        child.kill()?;
-    } else {
-        child.wait()?;
+        print_interleaved(&logs, new_lines);
+
+        if last_checked.elapsed() >= FOLLOW_CHECK_INTERVAL {
+            last_checked = Instant::now();
+            let mut any_running = false;
+            for log in &logs {
+                any_running |= config
+                    .get_state(&log.project.name)?
+                    .is_some_and(|p| p.is_running());
+            }
+
+            if any_running {
+                last_running = last_checked;
+            } else if last_running.elapsed() >= FOLLOW_GRACE {
+                return Ok(());
+            }
+        }
+
+        std::thread::sleep(FOLLOW_POLL_INTERVAL);
+    }
+}
+
+fn print_interleaved(logs: &[ProjectLog], mut lines: Vec<(usize, String)>) {
+    // The timestamps have a fixed width, so they sort in the order they were written. When a
+    // project doesn't log them, the lines keep the order they were read in
+    if logs.iter().all(|log| log.project.logs_timestamps()) {
+        lines.sort_by(|(i, a), (j, b)| {
+            logs[*i]
+                .parse(a)
+                .timestamp
+                .cmp(&logs[*j].parse(b).timestamp)
+        });
     }
+    for (i, line) in lines {
+        logs[i].print(&line);
+    }
+}
+
+fn config_show() -> Result<(), anyhow::Error> {
+    #[derive(Serialize)]
+    struct ResolvedConfig<'a> {
+        env: &'a HashMap<String, String>,
+        project: &'a [Project],
+    }
+
+    let config = WorkerConfig::new()?;
+    print!(
+        "{}",
+        toml::to_string_pretty(&ResolvedConfig {
+            env: &config.env,
+            project: &config.projects,
+        })?
+    );
 
     Ok(())
 }
 
 #[derive(Clone, Debug, Eq, PartialEq)]
 enum ActionArg {
-    Project(Project),
+    Project(Box<Project>),
     Group(Vec<Project>),
 }
 
 #[derive(Clone, Debug, Eq, PartialEq)]
 enum ActionArgRunning {
-    Project(RunningProject),
+    Project(Box<RunningProject>),
     Group(Vec<RunningProject>),
 }
 
@@ -129,8 +573,25 @@ struct ActionArgs {
 }
 
 #[derive(Debug, Parser)]
-struct ActionArgsRunning {
+struct StopArgs {
     projects: Vec<ActionArgRunning>,
+
+    /// Send the kill signal right away instead of waiting for the project to stop
+    #[arg(short, long)]
+    force: bool,
+
+    /// Stop every project depending on the project(s) first
+    #[arg(long)]
+    cascade: bool,
+}
+
+#[derive(Debug, Parser)]
+struct RestartArgs {
+    projects: Vec<ActionArgRunning>,
+
+    /// Restart every project depending on the project(s) as well
+    #[arg(long)]
+    cascade: bool,
 }
 
 #[derive(Debug, Parser)]
@@ -173,8 +634,13 @@ struct StartArgs {
         .args(["project", "cmd"])
 ))]
 struct RunArgs {
-    #[arg(value_name = "PROJECT", id = "project", conflicts_with_all = ["cmd", "name"])]
-    project: Option<Project>,
+    #[arg(
+        value_name = "PROJECT",
+        id = "project",
+        conflicts_with_all = ["cmd", "name"],
+        value_parser = |s: &str| s.parse::<Project>().map(Box::new)
+    )]
+    project: Option<Box<Project>>,
 
     /// One-off cmd (mutually exclusive with PROJECT)
     #[arg(
@@ -199,24 +665,116 @@ struct RunArgs {
 
 #[derive(Debug, Parser)]
 struct LogsArgs {
-    project: RunningProject,
+    /// Project(s) or group(s) to print the logs of
+    #[arg(required = true)]
+    projects: Vec<ActionArgRunning>,
+
     #[arg(short, long)]
     follow: bool,
 
     #[arg(short, long = "lines", default_value = "50")]
-    number: i32,
+    number: usize,
+
+    #[arg(short, long, help = "Print the time each line was written")]
+    timestamps: bool,
+
+    #[arg(
+        long,
+        help = "Only print output written to stdout",
+        conflicts_with = "stderr"
+    )]
+    stdout: bool,
+
+    #[arg(long, help = "Only print output written to stderr")]
+    stderr: bool,
+
+    /// Only print lines matching the regex
+    #[arg(long, value_name = "REGEX")]
+    grep: Option<Regex>,
+
+    /// Only print lines not matching the regex given to `--grep`
+    #[arg(long, short = 'v', requires = "grep")]
+    invert: bool,
+
+    /// Only print lines written after this time, e.g. `10m` ago or `2024-04-20 12:00:00` (UTC)
+    #[arg(long, value_name = "TIME", value_parser = parse_time)]
+    since: Option<SystemTime>,
+
+    /// Only print lines written before this time, e.g. `10m` ago or `2024-04-20 12:00:00` (UTC)
+    #[arg(long, value_name = "TIME", value_parser = parse_time)]
+    until: Option<SystemTime>,
+}
+
+#[derive(Debug, Parser)]
+struct HistoryArgs {
+    /// Name of the project
+    project: String,
+
+    #[arg(short, long = "lines", default_value = "20")]
+    number: usize,
+}
+
+#[derive(Debug, Parser)]
+struct SignalArgs {
+    project: ActionArgRunning,
+
+    /// Name or number of the signal, e.g. `SIGHUP`, `usr1` or `10`
+    signal: Signal,
+}
+
+#[derive(Debug, Parser)]
+struct ReloadArgs {
+    projects: Vec<ActionArgRunning>,
+}
+
+#[derive(Debug, Parser)]
+struct AttachArgs {
+    /// Name of the project, which needs `stdin = true`
+    project: String,
+}
+
+#[derive(Debug, Parser)]
+struct GraphArgs {
+    /// Only print the dependencies of these projects or groups
+    projects: Vec<ActionArg>,
+
+    #[arg(long, help = "Print the graph in the DOT format of graphviz")]
+    dot: bool,
 }
 
 #[derive(Debug, Parser)]
 struct StatusArgs {
-    #[arg(short, long, help = "Only print name of the project")]
+    #[arg(
+        short,
+        long,
+        help = "Only print name of the project",
+        conflicts_with = "format"
+    )]
     quiet: bool,
+
+    #[arg(
+        short,
+        long,
+        help = "Include projects that are not running, and how they exited"
+    )]
+    all: bool,
+
+    #[arg(long, value_enum, default_value_t)]
+    format: Format,
 }
 
 #[derive(Debug, Parser)]
 struct ListArgs {
-    #[arg(short, long, help = "Only print name of the project")]
+    #[arg(
+        short,
+        long,
+        help = "Only print name of the project",
+        conflicts_with = "format"
+    )]
     quiet: bool,
+
+    #[arg(long, value_enum, default_value_t)]
+    format: Format,
 }
 
 #[derive(Parser, Debug)]
@@ -224,17 +782,47 @@ enum SubCommands {
     /// Start the specified project(s). E.g. `worker start foo bar`
     Start(StartArgs),
     /// Stop the specified project(s). E.g. `worker stop foo bar`
-    Stop(ActionArgsRunning),
+    Stop(StopArgs),
     /// Restart the specified project(s). E.g. `worker restart foo bar` (Same as running stop and then start)
-    Restart(ActionArgsRunning),
+    Restart(RestartArgs),
     /// Runs the project in the foreground
     Run(RunArgs),
     /// Print out a status of which projects is running
     Status(StatusArgs),
     /// Print out a list of available projects to run
     List(ListArgs),
-    /// Print out logs for the specified project.
+    /// Print out logs for the specified project(s). E.g. `worker logs foo bar`
     Logs(LogsArgs),
+    /// Print out the dependency tree of the projects
+    Graph(GraphArgs),
+    /// Print out the previous runs of a project, and how they exited
+    History(HistoryArgs),
+    /// Send a signal to the specified project or group. E.g. `worker signal foo SIGHUP`
+    Signal(SignalArgs),
+    /// Send the reload signal of the specified project(s), instead of restarting them
+    Reload(ReloadArgs),
+    /// Send what is typed to the stdin of a running project, and print its output
+    Attach(AttachArgs),
+    /// Show the projects and their logs, and start, stop or restart them with keystrokes
+    Ui,
+    /// Run a daemon that owns the projects started while it runs, and serves the other commands
+    Daemon,
+    /// Check the config for mistakes, or print it as it is loaded
+    Config {
+        #[command(subcommand)]
+        command: ConfigCommand,
+    },
+    /// Supervise a project started by the daemon, which is read from stdin
+    #[command(hide = true)]
+    Supervise,
+}
+
+#[derive(Parser, Debug)]
+enum ConfigCommand {
+    /// Check the config files for mistakes, and print where they are
+    Validate,
+    /// Print the config with the included files merged and the variables expanded
+    Show,
 }
 
 #[derive(Parser, Debug)]
@@ -246,19 +834,38 @@ struct Cli {
 fn main() -> Result<(), anyhow::Error> {
     let cli = Cli::parse();
 
+    // Works on the config files directly, so they can be checked even when they can't be loaded
+    if let SubCommands::Config { command } = cli.subcommand {
+        return match command {
+            ConfigCommand::Validate => validate::validate(),
+            ConfigCommand::Show => config_show(),
+        };
+    }
+
     let config = WorkerConfig::new()?;
 
     let unique = |projects: Vec<ActionArg>| {
         projects
             .into_iter()
             .flat_map(|it| match it {
-                ActionArg::Project(project) => vec![project],
+                ActionArg::Project(project) => vec![*project],
                 ActionArg::Group(vec) => vec,
             })
             .unique()
             .collect()
     };
 
+    let unique_running = |projects: Vec<ActionArgRunning>| {
+        projects
+            .into_iter()
+            .flat_map(|it| match it {
+                ActionArgRunning::Project(project) => vec![*project],
+                ActionArgRunning::Group(vec) => vec,
+            })
+            .unique()
+            .collect()
+    };
+
     match cli.subcommand {
         SubCommands::Start(args) => {
             let projects = match (args.projects, args.name, args.cmd) {
@@ -269,32 +876,20 @@ fn main() -> Result<(), anyhow::Error> {
 
             start(&config, projects)?
         }
-        SubCommands::Stop(args) => stop(
-            &config,
-            args.projects
-                .into_iter()
-                .flat_map(|it| match it {
-                    ActionArgRunning::Project(project) => vec![project],
-                    ActionArgRunning::Group(vec) => vec,
-                })
-                .unique()
-                .collect(),
-        )?,
-
-        SubCommands::Restart(args) => restart(
-            &config,
-            args.projects
-                .into_iter()
-                .flat_map(|it| match it {
-                    ActionArgRunning::Project(project) => vec![project],
-                    ActionArgRunning::Group(vec) => vec,
-                })
-                .unique()
-                .collect(),
-        )?,
+        SubCommands::Stop(args) => {
+            let projects = unique_running(args.projects);
+            if args.cascade {
+                stop_cascade(&config, projects, args.force)?;
+            } else {
+                stop(&config, projects, args.force)?;
+            }
+        }
+        SubCommands::Restart(args) => {
+            restart(&config, unique_running(args.projects), args.cascade)?
+        }
         SubCommands::Run(args) => {
             let project = match (args.project, args.name, args.cmd) {
-                (Some(project), None, None) => project,
+                (Some(project), None, None) => *project,
                 (None, Some(name), Some(command)) => Project::from_cmd(name, command),
                 _ => unreachable!("Only one of project or command should be specified"),
             };
@@ -302,7 +897,26 @@ fn main() -> Result<(), anyhow::Error> {
         }
         SubCommands::Status(args) => status(&config, args)?,
         SubCommands::List(args) => list(&config, args)?,
-        SubCommands::Logs(args) => logs(&config, args)?,
+        SubCommands::Logs(mut args) => {
+            let projects = unique_running(std::mem::take(&mut args.projects));
+            logs(&config, projects, args)?
+        }
+        SubCommands::Graph(args) => graph(&config, args)?,
+        SubCommands::History(args) => history(&config, args)?,
+        SubCommands::Signal(args) => {
+            for project in unique_running(vec![args.project]) {
+                project.signal(&config, &args.signal)?;
+            }
+        }
+        SubCommands::Reload(args) => reload(&config, unique_running(args.projects))?,
+        SubCommands::Attach(args) => attach::attach(&config, &args.project)?,
+        SubCommands::Ui => ui::run(&config)?,
+        SubCommands::Config { .. } => unreachable!("Handled before loading the config"),
+        SubCommands::Daemon => daemon::serve(config)?,
+        SubCommands::Supervise => {
+            let project: Project = serde_json::from_reader(std::io::stdin())?;
+            std::process::exit(project.run_supervisor(&config));
+        }
     }
 
     Ok(())
@@ -1,47 +1,165 @@
 use std::{
     collections::HashMap,
-    fs::OpenOptions,
+    fs::{File, OpenOptions},
     hash::Hash,
-    os::{
-        fd::{FromRawFd, IntoRawFd},
-        unix::process::CommandExt,
-    },
-    process::Stdio,
+    io::{PipeWriter, Write},
+    os::fd::OwnedFd,
+    process::{Child, ExitStatus, Stdio},
     str::FromStr,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant, SystemTime},
 };
 
 use anyhow::{anyhow, Context};
 use serde::{Deserialize, Serialize};
 
 use crate::{
+    command::{ProjectCommand, Shell},
     config::WorkerConfig,
-    libc::{fork, has_processes_running, setsid, stop_pg, waitpid, Fork, Signal},
+    daemon::Client,
+    env::{project_env, EnvFiles},
+    history::Run,
+    libc::{
+        detach_stdio, fork, handle_signal, has_processes_running, mkfifo, openpty, setsid, stop_pg,
+        waitpid, Fork, Signal,
+    },
+    log::{copy, LogConfig, LogWriter, Stream},
+    ready::Ready,
+    watch::WatchConfig,
 };
 
+// A run lasting longer than this is considered healthy, and resets the retry counter
+const RESTART_RESET_AFTER: Duration = Duration::from_secs(10);
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+const DEFAULT_STOP_TIMEOUT: u64 = 5;
+
+static STOP_REQUESTED: AtomicBool = AtomicBool::new(false);
+
+extern "C" fn request_stop(_: i32) {
+    STOP_REQUESTED.store(true, Ordering::SeqCst);
+}
+
+extern "C" fn ignore(_: i32) {}
+
+// Signals that end or suspend a process unless handled. They are sent to the whole process group
+// of the project by `worker signal`, so the supervisor ignores the ones that are not used to stop
+// the project. The handlers are reset when the command starts, so it still gets them as usual
+const SURVIVED_SIGNALS: [Signal; 18] = [
+    Signal::SIGHUP,
+    Signal::SIGINT,
+    Signal::SIGQUIT,
+    Signal::SIGUSR1,
+    Signal::SIGUSR2,
+    Signal::SIGALRM,
+    Signal::SIGTERM,
+    Signal::SIGSTKFLT,
+    Signal::SIGTSTP,
+    Signal::SIGTTIN,
+    Signal::SIGTTOU,
+    Signal::SIGXCPU,
+    Signal::SIGXFSZ,
+    Signal::SIGVTALRM,
+    Signal::SIGPROF,
+    Signal::SIGIO,
+    Signal::SIGPWR,
+    Signal::SIGABRT,
+];
+
+// Signals reporting a fault of the process that gets them. The supervisor can't survive them, as
+// ignoring a real fault would make it loop forever, so they are never sent to the process group
+const FAULT_SIGNALS: [Signal; 6] = [
+    Signal::SIGSEGV,
+    Signal::SIGBUS,
+    Signal::SIGFPE,
+    Signal::SIGILL,
+    Signal::SIGTRAP,
+    Signal::SIGSYS,
+];
+
+/// When the supervisor should start the command again after it exits
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, Eq, PartialEq, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum RestartPolicy {
+    #[default]
+    Never,
+    OnFailure,
+    Always,
+}
+
+impl RestartPolicy {
+    fn should_restart(&self, status: &ExitStatus) -> bool {
+        match self {
+            RestartPolicy::Never => false,
+            RestartPolicy::OnFailure => !status.success(),
+            RestartPolicy::Always => true,
+        }
+    }
+}
+
+/// Where the command reads its stdin from and writes its stdout and stderr
+struct ChildStdio {
+    stdin: Option<OwnedFd>,
+    stdout: OwnedFd,
+    stderr: OwnedFd,
+}
+
 /// Project deserialized from config file
 #[derive(Deserialize, Serialize, Clone, Debug, Eq, PartialEq, Default)]
 pub struct Project {
     pub name: String,
-    pub command: Vec<String>,
+    pub command: ProjectCommand,
+    pub shell: Option<Shell>,
+    /// Relative to the directory of the config file, which is also the default
+    #[serde(default)]
     pub cwd: String,
     pub display: Option<String>,
     pub stop_signal: Option<Signal>,
+    pub reload_signal: Option<Signal>,
     pub envs: Option<HashMap<String, String>>,
+    pub env_file: Option<EnvFiles>,
     pub group: Option<Vec<String>>,
     pub dependencies: Option<Vec<String>>,
+    pub restart: Option<RestartPolicy>,
+    pub max_retries: Option<u32>,
+    pub stop_timeout: Option<u64>,
+    pub kill_signal: Option<Signal>,
+    pub ready: Option<Ready>,
+    pub log: Option<LogConfig>,
+    pub watch: Option<WatchConfig>,
+    pub pty: Option<bool>,
+    pub stdin: Option<bool>,
+    /// Why the project can't be started, e.g. a variable that isn't set. Kept on the project
+    /// instead of failing the config, so the other projects can still be used
+    #[serde(skip)]
+    pub error: Option<String>,
 }
 
 /// Project with process id
 #[derive(Deserialize, Serialize, Clone, Debug, Eq, PartialEq)]
 pub struct RunningProject {
     pub name: String,
-    pub command: Vec<String>,
+    pub command: ProjectCommand,
+    pub shell: Option<Shell>,
     pub cwd: String,
     pub display: Option<String>,
     pub stop_signal: Option<Signal>,
+    pub reload_signal: Option<Signal>,
     pub envs: Option<HashMap<String, String>>,
+    pub env_file: Option<EnvFiles>,
     pub group: Option<Vec<String>>,
     pub dependencies: Option<Vec<String>>,
+    pub restart: Option<RestartPolicy>,
+    pub max_retries: Option<u32>,
+    pub stop_timeout: Option<u64>,
+    pub kill_signal: Option<Signal>,
+    pub ready: Option<Ready>,
+    pub log: Option<LogConfig>,
+    pub watch: Option<WatchConfig>,
+    pub pty: Option<bool>,
+    pub stdin: Option<bool>,
     pub pid: i32,
 }
 
@@ -49,21 +167,29 @@ impl Project {
     pub fn from_cmd(name: String, cmd: String) -> Self {
         Project {
             name,
-            command: vec!["sh".to_string(), "-c".to_string(), cmd],
+            command: ProjectCommand::from_shell(cmd),
             cwd: std::env::current_dir()
                 .unwrap()
                 .to_string_lossy()
                 .to_string(),
-            display: None,
-            stop_signal: None,
-            envs: None,
-            group: None,
-            dependencies: None,
+            ..Default::default()
         }
     }
 
     pub fn start(&self, config: &WorkerConfig) -> Result<(), anyhow::Error> {
-        self.start_dependencies(config)?;
+        self.check()?;
+        // Fails here instead of in the supervisor, where the error would only end up in the log
+        self.command.program()?;
+        project_env(self, config)?;
+
+        LogWriter::rotate(
+            &config.log_file(self),
+            &self.log.clone().unwrap_or_default(),
+        )?;
+
+        if let Some(daemon) = Client::connect(config) {
+            return daemon.start(self);
+        }
 
         match fork().expect("Couldn't fork") {
             Fork::Parent(p) => {
@@ -75,24 +201,7 @@ impl Project {
 
                 match fork().expect("Couldn't fork inner") {
                     Fork::Parent(_) => std::process::exit(0),
-                    Fork::Child => {
-                        // Create a raw filedescriptor to use to merge stdout and stderr
-                        let fd = OpenOptions::new()
-                            .write(true)
-                            .truncate(true)
-                            .create(true)
-                            .open(config.log_file(self))?
-                            .into_raw_fd();
-
-                        let _ = std::process::Command::new(&self.command[0])
-                            .args(&self.command[1..])
-                            .envs(self.envs.clone().unwrap_or_default())
-                            .current_dir(&self.cwd)
-                            .stdout(unsafe { Stdio::from_raw_fd(fd) })
-                            .stderr(unsafe { Stdio::from_raw_fd(fd) })
-                            .stdin(Stdio::null())
-                            .exec();
-                    }
+                    Fork::Child => std::process::exit(self.run_supervisor(config)),
                 };
             }
         };
@@ -100,22 +209,237 @@ impl Project {
         Ok(())
     }
 
-    pub fn start_dependencies(&self, config: &WorkerConfig) -> Result<(), anyhow::Error> {
-        if let Some(ref deps) = self.dependencies {
-            for dep in deps {
-                let project = Project::from_str(dep)?;
-                if !project.is_running(config)? {
-                    project.start(config)?;
+    /// Fails with the reason the project couldn't be resolved from the config, if any
+    pub fn check(&self) -> Result<(), anyhow::Error> {
+        match self.error {
+            Some(ref error) => Err(anyhow!("{error}")),
+            None => Ok(()),
+        }
+    }
+
+    /// Supervises the project in the current process, which should already be in the session of
+    /// the project. Returns the exit code of the supervisor
+    pub fn run_supervisor(&self, config: &WorkerConfig) -> i32 {
+        detach_stdio().expect("Couldn't detach stdio");
+        match self.supervise(config) {
+            Ok(_) => 0,
+            Err(_) => 1,
+        }
+    }
+
+    // Runs the command and stays around as long as it should be kept alive according to the
+    // restart policy. Lives in the same process group as the command, so a stop signal reaches
+    // both the supervisor and the command.
+    fn supervise(&self, config: &WorkerConfig) -> Result<(), anyhow::Error> {
+        let signal = self.stop_signal.as_ref().unwrap_or(&Signal::SIGINT);
+        for survived in SURVIVED_SIGNALS
+            .iter()
+            .filter(|s| *s != signal && Some(*s) != self.kill_signal.as_ref())
+        {
+            handle_signal(survived, ignore)
+                .map_err(|e| anyhow!("Couldn't install signal handler: {e}"))?;
+        }
+        handle_signal(signal, request_stop)
+            .map_err(|e| anyhow!("Couldn't install signal handler: {e}"))?;
+
+        let log = Arc::new(Mutex::new(LogWriter::open(
+            config.log_file(self),
+            &self.log.clone().unwrap_or_default(),
+        )?));
+
+        // The output goes through pipes owned by the supervisor, so it can be written to the
+        // log line by line, tagged with the stream it was written to
+        let (stdout_reader, stdout) = std::io::pipe()?;
+        let (stderr_reader, stderr) = std::io::pipe()?;
+        let mut readers: Vec<(Stream, OwnedFd)> = vec![
+            (Stream::Stdout, stdout_reader.into()),
+            (Stream::Stderr, stderr_reader.into()),
+        ];
+
+        // Behind a pseudo-terminal the command sees a terminal and keeps its colors, but both
+        // streams end up on the same terminal and are logged as stdout
+        let (mut stdio, terminal) = if self.pty.unwrap_or(false) {
+            let (master, slave) = openpty().map_err(|_| {
+                anyhow!(
+                    "Couldn't open a pseudo-terminal: {}",
+                    std::io::Error::last_os_error()
+                )
+            })?;
+            let terminal = master.try_clone()?;
+            readers.push((Stream::Stdout, master));
+            let stdio = ChildStdio {
+                stdin: None,
+                stdout: slave.try_clone()?,
+                stderr: slave,
+            };
+            (stdio, Some(terminal))
+        } else {
+            let stdio = ChildStdio {
+                stdin: None,
+                stdout: stdout.try_clone()?.into(),
+                stderr: stderr.try_clone()?.into(),
+            };
+            (stdio, None)
+        };
+
+        // What `worker attach` writes to the FIFO goes to the command, through the terminal if it
+        // has one
+        if self.stdin.unwrap_or(false) {
+            let fifo = self.open_stdin(config)?;
+            stdio.stdin = Some(match terminal {
+                Some(terminal) => {
+                    std::thread::spawn(move || {
+                        std::io::copy(&mut File::from(fifo), &mut File::from(terminal))
+                    });
+                    stdio.stdout.try_clone()?
                 }
+                None => fifo,
+            });
+        }
+
+        let pumps: Vec<_> = readers
+            .into_iter()
+            .map(|(stream, reader)| {
+                let log = log.clone();
+                std::thread::spawn(move || copy(&log, stream, File::from(reader)))
+            })
+            .collect();
+
+        if let Some(ref watch) = self.watch {
+            if let Err(e) = watch.spawn(self, config, log.clone()) {
+                writeln!(&stderr, "worker: couldn't watch files: {e}")?;
             }
         }
-        Ok(())
+
+        let result = self.supervise_runs(config, &stdio, &stderr);
+
+        if self.stdin.unwrap_or(false) {
+            let _ = std::fs::remove_file(config.stdin_file(self));
+        }
+
+        // Everything is written to the log once every process holding the pipes has exited
+        drop(stdio);
+        drop(stdout);
+        drop(stderr);
+        for pump in pumps {
+            let _ = pump.join();
+        }
+
+        result
     }
 
-    pub fn run(&self) -> Result<(), anyhow::Error> {
-        let _ = std::process::Command::new(&self.command[0])
-            .args(&self.command[1..])
-            .envs(self.envs.clone().unwrap_or_default())
+    // Worker writes its own messages about the runs to stderr
+    fn supervise_runs(
+        &self,
+        config: &WorkerConfig,
+        stdio: &ChildStdio,
+        mut stderr: &PipeWriter,
+    ) -> Result<(), anyhow::Error> {
+        let policy = self.restart.unwrap_or_default();
+        let mut retries = 0;
+        let mut crashes = 0;
+
+        loop {
+            let started = Instant::now();
+            let started_at = SystemTime::now();
+            let mut child = match self.spawn(config, stdio) {
+                Ok(child) => child,
+                Err(e) => {
+                    writeln!(stderr, "worker: couldn't start {}: {e}", self.name)?;
+                    return Err(e);
+                }
+            };
+            let status = child.wait()?;
+
+            let stopped = STOP_REQUESTED.load(Ordering::SeqCst);
+            if !stopped && !status.success() {
+                crashes += 1;
+            }
+            config.record_run(
+                self,
+                &Run::new(child.id(), started_at, &status, stopped, crashes),
+            )?;
+
+            if stopped || !policy.should_restart(&status) {
+                return Ok(());
+            }
+
+            if started.elapsed() >= RESTART_RESET_AFTER {
+                retries = 0;
+            }
+
+            if self.max_retries.is_some_and(|max| retries >= max) {
+                writeln!(
+                    stderr,
+                    "worker: {status}, giving up after {retries} retries"
+                )?;
+                return Ok(());
+            }
+
+            let backoff = Duration::from_secs(1 << retries.min(5)).min(MAX_BACKOFF);
+            retries += 1;
+            writeln!(
+                stderr,
+                "worker: {status}, restarting in {}s",
+                backoff.as_secs()
+            )?;
+
+            let deadline = Instant::now() + backoff;
+            while Instant::now() < deadline {
+                if STOP_REQUESTED.load(Ordering::SeqCst) {
+                    return Ok(());
+                }
+                std::thread::sleep(Duration::from_millis(100));
+            }
+        }
+    }
+
+    // Creates the FIFO the stdin of the project is read from. It is opened for writing as well, so
+    // the command doesn't see the end of its input when `worker attach` detaches
+    fn open_stdin(&self, config: &WorkerConfig) -> Result<OwnedFd, anyhow::Error> {
+        let path = config.stdin_file(self);
+        let _ = std::fs::remove_file(&path);
+        mkfifo(&path).map_err(|_| {
+            anyhow!(
+                "Couldn't create {}: {}",
+                path.display(),
+                std::io::Error::last_os_error()
+            )
+        })?;
+
+        Ok(OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(&path)?
+            .into())
+    }
+
+    fn spawn(&self, config: &WorkerConfig, stdio: &ChildStdio) -> Result<Child, anyhow::Error> {
+        let stdin = match stdio.stdin {
+            Some(ref stdin) => Stdio::from(stdin.try_clone()?),
+            None => Stdio::null(),
+        };
+
+        let (program, args) = self.command.program()?;
+        let child = std::process::Command::new(program)
+            .args(args)
+            .envs(project_env(self, config)?)
+            .current_dir(&self.cwd)
+            .stdout(stdio.stdout.try_clone()?)
+            .stderr(stdio.stderr.try_clone()?)
+            .stdin(stdin)
+            .spawn()?;
+
+        Ok(child)
+    }
+
+    pub fn run(&self, config: &WorkerConfig) -> Result<(), anyhow::Error> {
+        self.check()?;
+
+        let (program, args) = self.command.program()?;
+        let _ = std::process::Command::new(program)
+            .args(args)
+            .envs(project_env(self, config)?)
             .current_dir(&self.cwd)
             .stdin(Stdio::null())
             .stdout(Stdio::inherit())
@@ -125,15 +449,92 @@ impl Project {
         Ok(())
     }
 
+    pub fn wait_until_ready(
+        &self,
+        config: &WorkerConfig,
+        started_at: Option<SystemTime>,
+    ) -> Result<(), anyhow::Error> {
+        match self.ready {
+            Some(ref ready) => ready.wait(self, config, started_at),
+            None => Ok(()),
+        }
+    }
+
     pub fn is_running(&self, config: &WorkerConfig) -> Result<bool, anyhow::Error> {
         Ok(config.running()?.iter().any(|it| it.name == self.name))
     }
 }
 
 impl RunningProject {
-    pub fn stop(&self) -> Result<(), anyhow::Error> {
+    pub fn new(project: Project, pid: i32) -> Self {
+        Self {
+            name: project.name,
+            command: project.command,
+            shell: project.shell,
+            cwd: project.cwd,
+            display: project.display,
+            stop_signal: project.stop_signal,
+            reload_signal: project.reload_signal,
+            envs: project.envs,
+            env_file: project.env_file,
+            group: project.group,
+            dependencies: project.dependencies,
+            restart: project.restart,
+            max_retries: project.max_retries,
+            stop_timeout: project.stop_timeout,
+            kill_signal: project.kill_signal,
+            ready: project.ready,
+            log: project.log,
+            watch: project.watch,
+            pty: project.pty,
+            stdin: project.stdin,
+            pid,
+        }
+    }
+
+    pub fn stop(&self, config: &WorkerConfig) -> Result<(), anyhow::Error> {
         let signal = self.stop_signal.as_ref().unwrap_or(&Signal::SIGINT);
-        stop_pg(self.pid, signal).map_err(|e| anyhow!("Error trying to stop project: {e}"))
+        self.send(config, signal)
+            .map_err(|e| anyhow!("Error trying to stop project: {e}"))
+    }
+
+    pub fn kill(&self, config: &WorkerConfig) -> Result<(), anyhow::Error> {
+        let signal = self.kill_signal.as_ref().unwrap_or(&Signal::SIGKILL);
+        self.send(config, signal)
+            .map_err(|e| anyhow!("Error trying to kill project: {e}"))
+    }
+
+    pub fn signal(&self, config: &WorkerConfig, signal: &Signal) -> Result<(), anyhow::Error> {
+        if FAULT_SIGNALS.contains(signal) {
+            return Err(anyhow!(
+                "{signal:?} can't be sent to {self}, since it would kill its supervisor as well"
+            ));
+        }
+
+        self.send(config, signal)
+            .map_err(|e| anyhow!("Error trying to signal project: {e}"))
+    }
+
+    /// Sends the reload signal of the project, which tells it to reload without a restart
+    pub fn reload(&self, config: &WorkerConfig) -> Result<(), anyhow::Error> {
+        let signal = self
+            .reload_signal
+            .as_ref()
+            .ok_or_else(|| anyhow!("{self} has no reload_signal"))?;
+        self.signal(config, signal)
+    }
+
+    // Signals the process group of the project, through the daemon if there is one
+    fn send(&self, config: &WorkerConfig, signal: &Signal) -> Result<(), anyhow::Error> {
+        match Client::connect(config) {
+            Some(daemon) => daemon.signal(&self.name, signal),
+            None => stop_pg(self.pid, signal).map_err(|e| anyhow!("{e}")),
+        }
+    }
+
+    /// How long to wait after the stop signal before escalating to the kill signal
+    pub fn stop_timeout(&self) -> Duration {
+        Duration::from_secs(self.stop_timeout.unwrap_or(DEFAULT_STOP_TIMEOUT))
     }
 
     pub fn is_running(&self) -> bool {
@@ -146,18 +547,34 @@ impl From<RunningProject> for Project {
         Self {
             name: value.name,
             command: value.command,
+            shell: value.shell,
             cwd: value.cwd,
             display: value.display,
             stop_signal: value.stop_signal,
+            reload_signal: value.reload_signal,
             envs: value.envs,
+            env_file: value.env_file,
             group: value.group,
             dependencies: value.dependencies,
+            restart: value.restart,
+            max_retries: value.max_retries,
+            stop_timeout: value.stop_timeout,
+            kill_signal: value.kill_signal,
+            ready: value.ready,
+            log: value.log,
+            watch: value.watch,
+            pty: value.pty,
+            stdin: value.stdin,
+            error: None,
         }
     }
 }
 
 pub trait WorkerProject {
     fn name(&self) -> &str;
+
+    /// Whether the lines in the log file start with the time they were written
+    fn logs_timestamps(&self) -> bool;
 }
 
 impl Hash for Project {
@@ -192,6 +609,12 @@ macro_rules! impl_worker_project {
             fn name(&self) -> &str {
                 &self.name
             }
+
+            fn logs_timestamps(&self) -> bool {
+                self.log
+                    .as_ref()
+                    .is_some_and(|log| log.timestamps == Some(true))
+            }
         }
     };
 }
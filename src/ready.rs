@@ -0,0 +1,177 @@
+use std::{
+    io::{Read, Write},
+    net::{SocketAddr, TcpStream, ToSocketAddrs},
+    process::Stdio,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{anyhow, Context};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config::WorkerConfig,
+    env::project_env,
+    log::LogLine,
+    project::{Project, WorkerProject},
+};
+
+const DEFAULT_TIMEOUT: u64 = 30;
+const INTERVAL: Duration = Duration::from_millis(250);
+const PROBE_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Readiness check deserialized from the `ready` block of a project. Every check that is set
+/// needs to pass before the project is considered ready
+#[derive(Deserialize, Serialize, Clone, Debug, Eq, PartialEq, Default)]
+pub struct Ready {
+    /// TCP port on localhost that accepts connections
+    pub port: Option<u16>,
+    /// URL that responds to a GET request with a 2xx or 3xx status
+    pub http: Option<String>,
+    /// Regex that matches a line in the log file of the project
+    pub log: Option<String>,
+    /// Shell command that exits successfully
+    pub cmd: Option<String>,
+    /// Seconds to wait before giving up
+    pub timeout: Option<u64>,
+}
+
+impl Ready {
+    /// Waits until the project is ready. `started_at` is when the project was just started, so
+    /// the log of a previous run isn't mistaken for the current one
+    pub fn wait(
+        &self,
+        project: &Project,
+        config: &WorkerConfig,
+        started_at: Option<SystemTime>,
+    ) -> Result<(), anyhow::Error> {
+        let timeout = Duration::from_secs(self.timeout.unwrap_or(DEFAULT_TIMEOUT));
+        let log = self
+            .log
+            .as_deref()
+            .map(Regex::new)
+            .transpose()
+            .with_context(|| format!("Invalid log regex for {}", project))?;
+
+        let start = Instant::now();
+        loop {
+            if self.is_ready(project, config, log.as_ref(), started_at)? {
+                return Ok(());
+            }
+
+            if !project.is_running(config)? {
+                return Err(anyhow!("{} exited before it was ready", project));
+            }
+
+            if start.elapsed() >= timeout {
+                return Err(anyhow!(
+                    "{} was not ready within {}s",
+                    project,
+                    timeout.as_secs()
+                ));
+            }
+
+            std::thread::sleep(INTERVAL);
+        }
+    }
+
+    fn is_ready(
+        &self,
+        project: &Project,
+        config: &WorkerConfig,
+        log: Option<&Regex>,
+        started_at: Option<SystemTime>,
+    ) -> Result<bool, anyhow::Error> {
+        if let Some(port) = self.port {
+            let addr = SocketAddr::from(([127, 0, 0, 1], port));
+            if TcpStream::connect_timeout(&addr, PROBE_TIMEOUT).is_err() {
+                return Ok(false);
+            }
+        }
+
+        if let Some(ref url) = self.http {
+            if !http_ok(url)? {
+                return Ok(false);
+            }
+        }
+
+        if let Some(regex) = log {
+            // The timestamps only have millisecond precision
+            let since = started_at
+                .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+                .map(|t| UNIX_EPOCH + Duration::from_millis(t.as_millis() as u64));
+
+            let content = std::fs::read_to_string(config.log_file(project)).unwrap_or_default();
+            if !content
+                .lines()
+                .map(|line| LogLine::parse(line, project.logs_timestamps()))
+                .any(|line| {
+                    since.is_none_or(|since| line.written().is_none_or(|written| written >= since))
+                        && regex.is_match(line.content)
+                })
+            {
+                return Ok(false);
+            }
+        }
+
+        if let Some(ref cmd) = self.cmd {
+            let status = std::process::Command::new("sh")
+                .args(["-c", cmd])
+                .current_dir(&project.cwd)
+                .envs(project_env(project, config)?)
+                .stdin(Stdio::null())
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .status()?;
+            if !status.success() {
+                return Ok(false);
+            }
+        }
+
+        Ok(true)
+    }
+}
+
+// Minimal HTTP/1.0 GET, only to check the status code of the response
+fn http_ok(url: &str) -> Result<bool, anyhow::Error> {
+    let rest = url
+        .strip_prefix("http://")
+        .ok_or_else(|| anyhow!("Only http:// urls are supported for readiness checks: {url}"))?;
+
+    let (authority, path) = match rest.find('/') {
+        Some(i) => (&rest[..i], &rest[i..]),
+        None => (rest, "/"),
+    };
+
+    let addr = if authority.contains(':') {
+        authority.to_string()
+    } else {
+        format!("{authority}:80")
+    };
+
+    let Some(addr) = addr.to_socket_addrs()?.next() else {
+        return Ok(false);
+    };
+
+    let Ok(mut stream) = TcpStream::connect_timeout(&addr, PROBE_TIMEOUT) else {
+        return Ok(false);
+    };
+    stream.set_read_timeout(Some(PROBE_TIMEOUT))?;
+    stream.set_write_timeout(Some(PROBE_TIMEOUT))?;
+
+    let request = format!("GET {path} HTTP/1.0\r\nHost: {authority}\r\nConnection: close\r\n\r\n");
+    if stream.write_all(request.as_bytes()).is_err() {
+        return Ok(false);
+    }
+
+    let mut response = Vec::new();
+    let _ = stream.read_to_end(&mut response);
+
+    // Status line looks like `HTTP/1.1 200 OK`
+    let status = String::from_utf8_lossy(&response)
+        .lines()
+        .next()
+        .and_then(|line| line.split_whitespace().nth(1)?.parse::<u16>().ok());
+
+    Ok(status.is_some_and(|code| (200..400).contains(&code)))
+}
@@ -0,0 +1,472 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    io::IsTerminal,
+    sync::mpsc::{self, Receiver, Sender},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::anyhow;
+use ratatui::{
+    crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers},
+    layout::{Constraint, Layout},
+    style::{Color, Modifier, Style},
+    text::Line,
+    widgets::{Block, Paragraph, Row, Table, TableState},
+    DefaultTerminal, Frame,
+};
+
+use crate::{
+    config::WorkerConfig,
+    format::{format_bytes, format_duration},
+    libc::{session_usage, SessionUsage},
+    log::{LogLine, LogReader},
+    project::{Project, RunningProject, WorkerProject},
+};
+
+const REFRESH_INTERVAL: Duration = Duration::from_secs(1);
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+// Number of lines of the log kept around for scrolling
+const MAX_LOG_LINES: usize = 5000;
+
+const HELP: &str = "↑/↓ select  s start  x stop  r restart  PgUp/PgDn scroll  End follow  q quit";
+
+/// The running projects and their resource usage, refreshed in the background since measuring
+/// the CPU usage takes a while
+struct Snapshot {
+    running: Vec<RunningProject>,
+    usage: HashMap<i32, SessionUsage>,
+}
+
+struct Ui<'a> {
+    config: &'a WorkerConfig,
+    snapshot: Snapshot,
+    table: TableState,
+    /// Name of the project the log is shown for, and the reader of its log
+    log: Option<(String, LogReader)>,
+    lines: VecDeque<String>,
+    /// Number of lines scrolled up from the end of the log
+    scroll: usize,
+    message: String,
+    messages: (Sender<String>, Receiver<String>),
+}
+
+pub fn run(config: &WorkerConfig) -> Result<(), anyhow::Error> {
+    if !std::io::stdout().is_terminal() {
+        return Err(anyhow!("worker ui needs to run in a terminal"));
+    }
+
+    let snapshots = refresh_in_background(config.clone());
+
+    let mut ui = Ui::new(config, config.running()?);
+
+    let mut terminal = ratatui::try_init()?;
+    let result = ui.run(&mut terminal, &snapshots);
+    ratatui::restore();
+
+    result
+}
+
+fn refresh_in_background(config: WorkerConfig) -> Receiver<Snapshot> {
+    let (tx, rx) = mpsc::channel();
+    std::thread::spawn(move || loop {
+        if let Ok(running) = config.running() {
+            let pids: Vec<_> = running.iter().map(|p| p.pid).collect();
+            let usage = session_usage(&pids);
+            if tx.send(Snapshot { running, usage }).is_err() {
+                return;
+            }
+        }
+        std::thread::sleep(REFRESH_INTERVAL);
+    });
+    rx
+}
+
+impl<'a> Ui<'a> {
+    fn new(config: &'a WorkerConfig, running: Vec<RunningProject>) -> Self {
+        Self {
+            config,
+            snapshot: Snapshot {
+                running,
+                usage: HashMap::new(),
+            },
+            table: TableState::default().with_selected(Some(0)),
+            log: None,
+            lines: VecDeque::new(),
+            scroll: 0,
+            message: HELP.to_string(),
+            messages: mpsc::channel(),
+        }
+    }
+
+    fn run(
+        &mut self,
+        terminal: &mut DefaultTerminal,
+        snapshots: &Receiver<Snapshot>,
+    ) -> Result<(), anyhow::Error> {
+        loop {
+            if let Some(snapshot) = snapshots.try_iter().last() {
+                self.snapshot = snapshot;
+            }
+            if let Some(message) = self.messages.1.try_iter().last() {
+                self.message = message;
+            }
+            self.read_log()?;
+
+            terminal.draw(|frame| self.draw(frame))?;
+
+            if !event::poll(POLL_INTERVAL)? {
+                continue;
+            }
+            let Event::Key(key) = event::read()? else {
+                continue;
+            };
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+
+            let projects = self.projects().len();
+            match key.code {
+                KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+                KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    return Ok(())
+                }
+                KeyCode::Up | KeyCode::Char('k') => self.select(|i| (i + projects - 1) % projects),
+                KeyCode::Down | KeyCode::Char('j') => self.select(|i| (i + 1) % projects),
+                KeyCode::Char('s') => self.action("start"),
+                KeyCode::Char('x') => self.action("stop"),
+                KeyCode::Char('r') => self.action("restart"),
+                KeyCode::PageUp => {
+                    self.scroll = (self.scroll + 10).min(self.lines.len().saturating_sub(1))
+                }
+                KeyCode::PageDown => self.scroll = self.scroll.saturating_sub(10),
+                KeyCode::End => self.scroll = 0,
+                _ => {}
+            }
+        }
+    }
+
+    // The projects from the config, and the running one-off commands
+    fn projects(&self) -> Vec<Project> {
+        let one_off = self
+            .snapshot
+            .running
+            .iter()
+            .filter(|r| !self.config.projects.iter().any(|p| p.name == r.name))
+            .map(|r| r.clone().into());
+
+        self.config
+            .projects
+            .iter()
+            .cloned()
+            .chain(one_off)
+            .collect()
+    }
+
+    fn selected(&self) -> Option<Project> {
+        self.projects().get(self.table.selected()?).cloned()
+    }
+
+    fn select(&mut self, next: impl Fn(usize) -> usize) {
+        if !self.projects().is_empty() {
+            self.table
+                .select(Some(next(self.table.selected().unwrap_or(0))));
+        }
+    }
+
+    // Runs the command in the background, like it was run from the shell, so its output doesn't
+    // end up in the middle of the ui
+    fn action(&mut self, action: &'static str) {
+        let Some(project) = self.selected() else {
+            return;
+        };
+
+        self.message = format!("Running worker {action} {project}...");
+
+        let tx = self.messages.0.clone();
+        std::thread::spawn(move || {
+            let message = match std::env::current_exe().and_then(|exe| {
+                std::process::Command::new(exe)
+                    .arg(action)
+                    .arg(&project.name)
+                    .output()
+            }) {
+                Ok(output) => String::from_utf8_lossy(&output.stderr)
+                    .lines()
+                    .chain(String::from_utf8_lossy(&output.stdout).lines())
+                    .rfind(|line| !line.trim().is_empty())
+                    .map(str::to_string)
+                    .unwrap_or_else(|| format!("worker {action} {project} finished")),
+                Err(e) => format!("Couldn't run worker {action} {project}: {e}"),
+            };
+            let _ = tx.send(message);
+        });
+    }
+
+    // Keeps reading the log of the selected project, and starts over when another is selected
+    fn read_log(&mut self) -> Result<(), anyhow::Error> {
+        let Some(project) = self.selected() else {
+            return Ok(());
+        };
+
+        if self
+            .log
+            .as_ref()
+            .is_none_or(|(name, _)| *name != project.name)
+        {
+            let reader = LogReader::new(self.config.log_file(&project));
+            self.log = Some((project.name.clone(), reader));
+            self.lines.clear();
+            self.scroll = 0;
+        }
+
+        if let Some((_, ref mut reader)) = self.log {
+            let timestamps = project.logs_timestamps();
+            let mut added = 0;
+            reader.read_lines(|line| {
+                if self.lines.len() == MAX_LOG_LINES {
+                    self.lines.pop_front();
+                }
+                self.lines
+                    .push_back(LogLine::parse(line, timestamps).format(false).to_string());
+                added += 1;
+            })?;
+
+            // Stay at the same place in the log when scrolled up
+            if self.scroll > 0 {
+                self.scroll = (self.scroll + added).min(self.lines.len().saturating_sub(1));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [table_area, log_area, message_area] = Layout::vertical([
+            Constraint::Percentage(40),
+            Constraint::Min(3),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+
+        let rows: Vec<_> = self
+            .projects()
+            .iter()
+            .map(|project| {
+                let running = self
+                    .snapshot
+                    .running
+                    .iter()
+                    .find(|r| r.name == project.name);
+                let usage = running.and_then(|r| self.snapshot.usage.get(&r.pid));
+
+                let (status, color) = match running {
+                    Some(_) => ("running", Color::Green),
+                    None => ("stopped", Color::DarkGray),
+                };
+
+                Row::new(vec![
+                    project.to_string(),
+                    status.to_string(),
+                    running.map(|r| r.pid.to_string()).unwrap_or_default(),
+                    usage.map(|u| format!("{:.1}%", u.cpu)).unwrap_or_default(),
+                    usage.map(|u| format_bytes(u.memory)).unwrap_or_default(),
+                    usage
+                        .map(|u| {
+                            format_duration(Duration::from_secs(now.saturating_sub(u.started_at)))
+                        })
+                        .unwrap_or_default(),
+                ])
+                .style(Style::default().fg(color))
+            })
+            .collect();
+
+        let table = Table::new(
+            rows,
+            [
+                Constraint::Fill(1),
+                Constraint::Length(8),
+                Constraint::Length(8),
+                Constraint::Length(7),
+                Constraint::Length(8),
+                Constraint::Length(8),
+            ],
+        )
+        .header(
+            Row::new(["NAME", "STATUS", "PID", "CPU", "MEM", "UPTIME"])
+                .style(Style::default().add_modifier(Modifier::BOLD)),
+        )
+        .row_highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+        .block(Block::bordered().title(" projects "));
+
+        frame.render_stateful_widget(table, table_area, &mut self.table);
+
+        // The lines that fit in the pane, ending `scroll` lines from the end of the log
+        let height = log_area.height.saturating_sub(2) as usize;
+        let end = self.lines.len() - self.scroll.min(self.lines.len());
+        let lines: Vec<_> = self
+            .lines
+            .range(end.saturating_sub(height)..end)
+            .map(|line| Line::raw(line.as_str()))
+            .collect();
+
+        let title = match (&self.log, self.scroll) {
+            (Some((name, _)), 0) => format!(" logs: {name} "),
+            (Some((name, _)), scroll) => format!(" logs: {name} ({scroll} lines up) "),
+            (None, _) => " logs ".to_string(),
+        };
+
+        frame.render_widget(
+            Paragraph::new(lines).block(Block::bordered().title(title)),
+            log_area,
+        );
+        frame.render_widget(Paragraph::new(self.message.as_str()), message_area);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use tempfile::TempDir;
+
+    use super::*;
+
+    fn project(name: &str) -> Project {
+        Project {
+            name: name.to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn config(dir: &TempDir, names: &[&str]) -> WorkerConfig {
+        let projects = names.iter().map(|name| project(name)).collect();
+        WorkerConfig::with_projects(dir.path(), projects, HashMap::new()).unwrap()
+    }
+
+    fn append_log(config: &WorkerConfig, name: &str, lines: &[&str]) {
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(config.log_file(&project(name)))
+            .unwrap();
+        for line in lines {
+            writeln!(file, "{line}").unwrap();
+        }
+    }
+
+    fn names(ui: &Ui) -> Vec<String> {
+        ui.projects().into_iter().map(|p| p.name).collect()
+    }
+
+    #[test]
+    fn projects_are_followed_by_running_one_off_commands() {
+        let dir = TempDir::new().unwrap();
+        let config = config(&dir, &["web", "db"]);
+        let running = vec![
+            RunningProject::new(project("db"), 1),
+            RunningProject::new(project("one-off"), 2),
+        ];
+
+        let ui = Ui::new(&config, running);
+
+        assert_eq!(names(&ui), ["web", "db", "one-off"]);
+    }
+
+    #[test]
+    fn select_wraps_around() {
+        let dir = TempDir::new().unwrap();
+        let config = config(&dir, &["web", "db", "worker"]);
+        let mut ui = Ui::new(&config, vec![]);
+        let projects = ui.projects().len();
+
+        ui.select(|i| (i + projects - 1) % projects);
+        assert_eq!(ui.selected().unwrap().name, "worker");
+
+        ui.select(|i| (i + 1) % projects);
+        assert_eq!(ui.selected().unwrap().name, "web");
+    }
+
+    #[test]
+    fn select_without_projects() {
+        let dir = TempDir::new().unwrap();
+        let config = config(&dir, &[]);
+        let mut ui = Ui::new(&config, vec![]);
+
+        ui.select(|i| i + 1);
+
+        assert_eq!(ui.table.selected(), Some(0));
+        assert!(ui.selected().is_none());
+    }
+
+    #[test]
+    fn read_log_follows_the_end() {
+        let dir = TempDir::new().unwrap();
+        let config = config(&dir, &["web"]);
+        let mut ui = Ui::new(&config, vec![]);
+
+        append_log(&config, "web", &["one", "two"]);
+        ui.read_log().unwrap();
+        append_log(&config, "web", &["three"]);
+        ui.read_log().unwrap();
+
+        assert_eq!(ui.lines, ["one", "two", "three"]);
+        assert_eq!(ui.scroll, 0);
+    }
+
+    #[test]
+    fn read_log_keeps_the_place_when_scrolled_up() {
+        let dir = TempDir::new().unwrap();
+        let config = config(&dir, &["web"]);
+        let mut ui = Ui::new(&config, vec![]);
+
+        append_log(&config, "web", &["one", "two", "three"]);
+        ui.read_log().unwrap();
+        ui.scroll = 1;
+        append_log(&config, "web", &["four", "five"]);
+        ui.read_log().unwrap();
+
+        assert_eq!(ui.scroll, 3);
+        assert_eq!(ui.lines[ui.lines.len() - 1 - ui.scroll], "two");
+    }
+
+    #[test]
+    fn read_log_starts_over_for_another_project() {
+        let dir = TempDir::new().unwrap();
+        let config = config(&dir, &["web", "db"]);
+        let mut ui = Ui::new(&config, vec![]);
+
+        append_log(&config, "web", &["web one", "web two"]);
+        append_log(&config, "db", &["db one"]);
+        ui.read_log().unwrap();
+        ui.scroll = 1;
+
+        ui.select(|i| i + 1);
+        ui.read_log().unwrap();
+
+        assert_eq!(ui.lines, ["db one"]);
+        assert_eq!(ui.scroll, 0);
+    }
+
+    #[test]
+    fn read_log_keeps_the_last_lines() {
+        let dir = TempDir::new().unwrap();
+        let config = config(&dir, &["web"]);
+        let mut ui = Ui::new(&config, vec![]);
+
+        let lines: Vec<_> = (0..MAX_LOG_LINES + 10).map(|i| i.to_string()).collect();
+        append_log(
+            &config,
+            "web",
+            &lines.iter().map(String::as_str).collect::<Vec<_>>(),
+        );
+        ui.read_log().unwrap();
+
+        assert_eq!(ui.lines.len(), MAX_LOG_LINES);
+        assert_eq!(ui.lines[0], "10");
+    }
+}
@@ -0,0 +1,262 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Display,
+    ops::Range,
+    path::PathBuf,
+};
+
+use anyhow::anyhow;
+use serde::Deserialize;
+use toml::Spanned;
+
+use crate::{
+    config::{config_files, merged_env, resolve_project, ConfigFile},
+    graph::DependencyGraph,
+    project::Project,
+};
+
+/// The parts of a config file that are validated, with the position they are written at
+#[derive(Deserialize)]
+struct RawConfig {
+    #[serde(default)]
+    project: Vec<RawProject>,
+}
+
+#[derive(Deserialize)]
+struct RawProject {
+    name: Spanned<String>,
+    command: Spanned<toml::Value>,
+    cwd: Option<Spanned<String>>,
+    group: Option<Vec<Spanned<String>>>,
+    dependencies: Option<Vec<Spanned<String>>>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum Severity {
+    Error,
+    Warning,
+}
+
+struct Diagnostic {
+    severity: Severity,
+    path: PathBuf,
+    // Line and column, starting at 1
+    position: Option<(usize, usize)>,
+    message: String,
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.path.display())?;
+        if let Some((line, column)) = self.position {
+            write!(f, ":{line}:{column}")?;
+        }
+
+        let severity = match self.severity {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        };
+        write!(f, ": {severity}: {}", self.message)
+    }
+}
+
+struct Diagnostics(Vec<Diagnostic>);
+
+impl Diagnostics {
+    fn push(
+        &mut self,
+        severity: Severity,
+        file: &ConfigFile,
+        span: Option<Range<usize>>,
+        message: String,
+    ) {
+        let position = span.map(|span| {
+            let before = &file.content[..span.start];
+            let line = before.matches('\n').count() + 1;
+            let column = before.len() - before.rfind('\n').map_or(0, |i| i + 1) + 1;
+            (line, column)
+        });
+
+        self.0.push(Diagnostic {
+            severity,
+            path: file.path.clone(),
+            position,
+            message,
+        });
+    }
+}
+
+/// Checks the config files for mistakes, and prints where they are. Fails if there are any errors
+pub fn validate() -> Result<(), anyhow::Error> {
+    let (_, files) = config_files()?;
+    let mut diagnostics = Diagnostics(vec![]);
+
+    let raw: Vec<RawConfig> = files
+        .iter()
+        .map(|file| toml::from_str(&file.content))
+        .collect::<Result<_, _>>()?;
+
+    let env = merged_env(&files);
+    for (file, config) in files.iter().zip(raw.iter()) {
+        validate_file(&mut diagnostics, file, config, &env);
+    }
+
+    // A project in a file overrides the project with the same name from a file before it
+    let mut projects: Vec<(&ConfigFile, &RawProject)> = vec![];
+    for (file, config) in files.iter().zip(raw.iter()) {
+        for project in config.project.iter() {
+            match projects
+                .iter_mut()
+                .find(|(_, p)| p.name.get_ref() == project.name.get_ref())
+            {
+                Some(existing) => *existing = (file, project),
+                None => projects.push((file, project)),
+            }
+        }
+    }
+
+    for (file, project) in projects.iter() {
+        for dependency in project.dependencies.iter().flatten() {
+            if !projects
+                .iter()
+                .any(|(_, p)| p.name.get_ref() == dependency.get_ref())
+            {
+                diagnostics.push(
+                    Severity::Error,
+                    file,
+                    Some(dependency.span()),
+                    format!(
+                        "{} depends on unknown project {}",
+                        project.name.get_ref(),
+                        dependency.get_ref()
+                    ),
+                );
+            }
+        }
+
+        // Groups are looked up before projects, so the project could never be picked by name
+        for group in project.group.iter().flatten() {
+            if projects
+                .iter()
+                .any(|(_, p)| p.name.get_ref() == group.get_ref())
+            {
+                diagnostics.push(
+                    Severity::Error,
+                    file,
+                    Some(group.span()),
+                    format!("Group {} has the same name as a project", group.get_ref()),
+                );
+            }
+        }
+    }
+
+    // Cycles can only be found once every dependency is known
+    if !diagnostics.0.iter().any(|d| d.severity == Severity::Error) {
+        let graph_projects: Vec<_> = projects
+            .iter()
+            .map(|(_, p)| Project {
+                name: p.name.get_ref().clone(),
+                dependencies: p
+                    .dependencies
+                    .as_ref()
+                    .map(|deps| deps.iter().map(|d| d.get_ref().clone()).collect()),
+                ..Default::default()
+            })
+            .collect();
+
+        if let Err(e) = DependencyGraph::new(&graph_projects) {
+            if let Some(file) = files.last() {
+                diagnostics.push(Severity::Error, file, None, e.to_string());
+            }
+        }
+    }
+
+    for diagnostic in diagnostics.0.iter() {
+        eprintln!("{diagnostic}");
+    }
+
+    let errors = diagnostics
+        .0
+        .iter()
+        .filter(|d| d.severity == Severity::Error)
+        .count();
+
+    match errors {
+        0 => {
+            println!("The config is valid");
+            Ok(())
+        }
+        1 => Err(anyhow!("Found 1 error in the config")),
+        n => Err(anyhow!("Found {n} errors in the config")),
+    }
+}
+
+fn validate_file(
+    diagnostics: &mut Diagnostics,
+    file: &ConfigFile,
+    config: &RawConfig,
+    env: &HashMap<String, String>,
+) {
+    let mut names = HashSet::new();
+
+    for (raw, project) in config.project.iter().zip(file.config.project.iter()) {
+        let name = raw.name.get_ref();
+
+        if name.trim().is_empty() {
+            diagnostics.push(
+                Severity::Error,
+                file,
+                Some(raw.name.span()),
+                "Project name is empty".to_string(),
+            );
+        } else if !names.insert(name) {
+            diagnostics.push(
+                Severity::Error,
+                file,
+                Some(raw.name.span()),
+                format!("Project {name} is defined more than once"),
+            );
+        }
+
+        if name.contains('-') {
+            diagnostics.push(
+                Severity::Warning,
+                file,
+                Some(raw.name.span()),
+                format!(
+                    "Project name {name} contains `-`, which separates the name from the pid in the state files"
+                ),
+            );
+        }
+
+        let empty = match raw.command.get_ref() {
+            toml::Value::String(command) => command.trim().is_empty(),
+            toml::Value::Array(args) => args.is_empty(),
+            _ => false,
+        };
+        if empty {
+            diagnostics.push(
+                Severity::Error,
+                file,
+                Some(raw.command.span()),
+                format!("Project {name} has an empty command"),
+            );
+        }
+
+        if let Err(e) = project.command.args() {
+            diagnostics.push(
+                Severity::Error,
+                file,
+                Some(raw.command.span()),
+                format!("Project {name} has an invalid command: {e:#}"),
+            );
+            continue;
+        }
+
+        // Resolving the project finds undefined variables and a missing cwd
+        if let Err(e) = resolve_project(&mut project.clone(), file.dir(), env) {
+            let span = raw.cwd.as_ref().unwrap_or(&raw.name).span();
+            diagnostics.push(Severity::Error, file, Some(span), format!("{e:#}"));
+        }
+    }
+}
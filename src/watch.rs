@@ -0,0 +1,169 @@
+use std::{
+    os::unix::process::CommandExt,
+    path::{Path, PathBuf},
+    process::Stdio,
+    sync::{
+        mpsc::{self, RecvTimeoutError},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+use anyhow::anyhow;
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config::WorkerConfig,
+    libc::setsid,
+    log::{LogWriter, Stream},
+    project::Project,
+};
+
+const DEFAULT_DEBOUNCE: u64 = 500;
+
+/// Restart the project when files change, deserialized from the `watch` block of a project
+#[derive(Deserialize, Serialize, Clone, Debug, Eq, PartialEq, Default)]
+pub struct WatchConfig {
+    /// Files or directories to watch, relative to the cwd of the project. Defaults to the cwd
+    pub paths: Option<Vec<String>>,
+    /// Only restart when a changed file matches one of these globs
+    pub include: Option<Vec<String>>,
+    /// Never restart when a changed file matches one of these globs
+    pub exclude: Option<Vec<String>>,
+    /// Milliseconds to wait for more changes before restarting
+    pub debounce: Option<u64>,
+}
+
+struct Filter {
+    cwd: PathBuf,
+    include: Option<GlobSet>,
+    exclude: Option<GlobSet>,
+    // Changes to the logs and state of worker itself never restart the project
+    worker_dir: PathBuf,
+}
+
+impl Filter {
+    fn matches(&self, path: &Path) -> bool {
+        if path.starts_with(&self.worker_dir) {
+            return false;
+        }
+
+        // The globs are relative to the cwd of the project
+        let path = path.strip_prefix(&self.cwd).unwrap_or(path);
+        self.include
+            .as_ref()
+            .is_none_or(|globs| globs.is_match(path))
+            && self
+                .exclude
+                .as_ref()
+                .is_none_or(|globs| !globs.is_match(path))
+    }
+}
+
+fn glob_set(globs: &Option<Vec<String>>) -> Result<Option<GlobSet>, anyhow::Error> {
+    let Some(globs) = globs else {
+        return Ok(None);
+    };
+
+    let mut builder = GlobSetBuilder::new();
+    for glob in globs {
+        builder.add(Glob::new(glob)?);
+    }
+    Ok(Some(builder.build()?))
+}
+
+impl WatchConfig {
+    /// Watches the files in a background thread of the supervisor, and restarts the project with
+    /// `worker restart` once they have changed
+    pub fn spawn(
+        &self,
+        project: &Project,
+        config: &WorkerConfig,
+        log: Arc<Mutex<LogWriter>>,
+    ) -> Result<(), anyhow::Error> {
+        let cwd = std::fs::canonicalize(&project.cwd)?;
+        let filter = Filter {
+            include: glob_set(&self.include)?,
+            exclude: glob_set(&self.exclude)?,
+            worker_dir: std::fs::canonicalize(config.worker_dir())?,
+            cwd: cwd.clone(),
+        };
+        let debounce = Duration::from_millis(self.debounce.unwrap_or(DEFAULT_DEBOUNCE));
+
+        let (tx, rx) = mpsc::channel();
+        let mut watcher = RecommendedWatcher::new(tx, notify::Config::default())?;
+        for path in self.paths.clone().unwrap_or_else(|| vec![".".to_string()]) {
+            watcher
+                .watch(&cwd.join(&path), RecursiveMode::Recursive)
+                .map_err(|e| anyhow!("Couldn't watch {path}: {e}"))?;
+        }
+
+        let name = project.name.clone();
+        let message = move |message: String| {
+            let mut log = log.lock().expect("Log writer lock poisoned");
+            let _ = log.write(Stream::Stderr, format!("worker: {message}\n").as_bytes());
+        };
+        std::thread::spawn(move || {
+            // Owned by the thread, since it stops watching when dropped
+            let _watcher = watcher;
+
+            let changed = |event: &notify::Result<notify::Event>| {
+                event.as_ref().is_ok_and(|event| {
+                    matches!(
+                        event.kind,
+                        EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_)
+                    ) && event.paths.iter().any(|path| filter.matches(path))
+                })
+            };
+
+            // Wait for the first change, and then until nothing has changed for a while. Other
+            // events, like the project writing its log, don't count as a change
+            while rx.iter().any(|event| changed(&event)) {
+                let mut deadline = Instant::now() + debounce;
+                loop {
+                    match rx.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+                        Ok(event) if changed(&event) => deadline = Instant::now() + debounce,
+                        Ok(_) => continue,
+                        Err(RecvTimeoutError::Timeout) => break,
+                        Err(RecvTimeoutError::Disconnected) => return,
+                    }
+                }
+
+                message("files changed, restarting".to_string());
+                match restart(&name) {
+                    // Restarting stops this supervisor, so there is nothing more to watch
+                    Ok(()) => return,
+                    Err(e) => {
+                        message(format!("couldn't restart {name}: {e}"));
+                    }
+                }
+            }
+        });
+
+        Ok(())
+    }
+}
+
+// Restarts the project through `worker restart`, in a session of its own so it isn't stopped
+// together with the project
+fn restart(name: &str) -> Result<(), std::io::Error> {
+    let mut cmd = std::process::Command::new(std::env::current_exe()?);
+    cmd.arg("restart")
+        .arg(name)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+
+    unsafe {
+        cmd.pre_exec(|| {
+            setsid().map_err(|_| std::io::Error::last_os_error())?;
+            Ok(())
+        });
+    }
+
+    cmd.spawn()?;
+
+    Ok(())
+}
@@ -0,0 +1,154 @@
+use std::{
+    io::Write,
+    process::{Child, Stdio},
+};
+
+use assert_cmd::cargo::cargo_bin;
+use common::{WorkerTestConfig, WorkerTestProject};
+use predicates::prelude::predicate;
+use uuid::Uuid;
+
+mod common;
+
+// Kills the terminal when the test ends, so a failing test doesn't leave it waiting for input
+struct Terminal(Child);
+
+impl Drop for Terminal {
+    fn drop(&mut self) {
+        let _ = self.0.kill();
+        let _ = self.0.wait();
+    }
+}
+
+// Echoes the lines it reads, and whether it reads them from a terminal
+const READING: &str = r#"
+    command = ["sh", "-c", "while read line; do if [ -t 0 ]; then echo \"tty $line\"; else echo \"got $line\"; fi; done"]
+    stdin = true
+"#;
+
+fn attach(worker: &WorkerTestConfig, name: &Uuid, input: &str) {
+    let mut cmd = worker.cmd("attach", &[&name.to_string()]);
+    cmd.write_stdin(input).assert().success();
+}
+
+#[test]
+fn test_attach_sends_input() {
+    let uuid = Uuid::new_v4();
+    let worker = WorkerTestConfig::with_project(&uuid, READING);
+
+    let mut cmd = worker.start(&[&uuid.to_string()]);
+    cmd.assert().success();
+
+    attach(&worker, &uuid, "first\n");
+    worker.wait_for_log(&uuid.to_string(), "got first");
+    // Detaching doesn't end the input of the project
+    attach(&worker, &uuid, "second\n");
+    worker.wait_for_log(&uuid.to_string(), "got second");
+    assert!(!worker.pids(&uuid.to_string()).unwrap().is_empty());
+
+    let mut cmd = worker.stop(&[&uuid.to_string()]);
+    cmd.assert().success();
+}
+
+#[test]
+fn test_attach_detaches_at_detach_key() {
+    let uuid = Uuid::new_v4();
+    let worker = WorkerTestConfig::with_project(&uuid, READING);
+
+    let mut cmd = worker.start(&[&uuid.to_string()]);
+    cmd.assert().success();
+
+    attach(&worker, &uuid, "sent\n\x1dnot sent\n");
+
+    let log = worker.wait_for_log(&uuid.to_string(), "got sent");
+    assert!(!log.contains("not sent"));
+
+    let mut cmd = worker.stop(&[&uuid.to_string()]);
+    cmd.assert().success();
+}
+
+#[test]
+fn test_attach_sends_keys_as_typed() {
+    let uuid = Uuid::new_v4();
+    let worker = WorkerTestConfig::with_project(
+        &uuid,
+        r#"
+        command = ["sh", "-c", "while true; do key=$(dd bs=1 count=1 2>/dev/null); echo \"key $key\"; done"]
+        stdin = true
+        "#,
+    );
+
+    let mut cmd = worker.start(&[&uuid.to_string()]);
+    cmd.assert().success();
+
+    // `script` runs the attach in a terminal, which gets the keys from its stdin
+    let attach = format!("{} attach {uuid}", cargo_bin("worker").display());
+    let mut terminal = Terminal(
+        std::process::Command::new("script")
+            .args(["-qec", &attach, "/dev/null"])
+            .current_dir(worker.path())
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .spawn()
+            .unwrap(),
+    );
+    let mut stdin = terminal.0.stdin.take().unwrap();
+
+    // Sent without waiting for Enter
+    stdin.write_all(b"r").unwrap();
+    worker.wait_for_log(&uuid.to_string(), "key r");
+
+    // Ctrl-C detaches instead of being sent
+    stdin.write_all(b"\x03").unwrap();
+    assert!(terminal.0.wait().unwrap().success());
+    assert!(!worker.pids(&uuid.to_string()).unwrap().is_empty());
+
+    let mut cmd = worker.stop(&[&uuid.to_string()]);
+    cmd.assert().success();
+}
+
+#[test]
+fn test_attach_through_pty() {
+    let uuid = Uuid::new_v4();
+    let worker = WorkerTestConfig::with_project(&uuid, &format!("{READING}\npty = true"));
+
+    let mut cmd = worker.start(&[&uuid.to_string()]);
+    cmd.assert().success();
+
+    attach(&worker, &uuid, "typed\n");
+
+    // The input isn't echoed into the log by the terminal
+    let log = worker.wait_for_log(&uuid.to_string(), "tty typed");
+    assert!(!log.lines().any(|line| line == "typed"));
+
+    let mut cmd = worker.stop(&[&uuid.to_string()]);
+    cmd.assert().success();
+}
+
+#[test]
+fn test_attach_requires_stdin() {
+    let worker = WorkerTestConfig::new();
+    let project = worker.project_name(&WorkerTestProject::One);
+
+    let mut cmd = worker.start(&[&project]);
+    cmd.assert().success();
+
+    let mut cmd = worker.cmd("attach", &[&project]);
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("stdin = true"));
+
+    let mut cmd = worker.stop(&[&project]);
+    cmd.assert().success();
+}
+
+#[test]
+fn test_attach_not_running() {
+    let uuid = Uuid::new_v4();
+    let worker = WorkerTestConfig::with_project(&uuid, READING);
+
+    let mut cmd = worker.cmd("attach", &[&uuid.to_string()]);
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("is not running"));
+}
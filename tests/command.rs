@@ -0,0 +1,82 @@
+use common::WorkerTestConfig;
+use predicates::prelude::predicate;
+use uuid::Uuid;
+
+mod common;
+
+#[test]
+fn test_command_string_is_split_like_a_shell() {
+    let uuid = Uuid::new_v4();
+    let worker = WorkerTestConfig::with_project(
+        &uuid,
+        r#"command = "printf '%s|' 'one two' \"three  four\" five\\ six""#,
+    );
+
+    let mut cmd = worker.run(&[&uuid.to_string()]);
+    cmd.assert()
+        .success()
+        .stdout("one two|three  four|five six|");
+}
+
+#[test]
+fn test_command_string_without_shell_is_not_interpreted() {
+    let uuid = Uuid::new_v4();
+    let worker = WorkerTestConfig::with_project(&uuid, r#"command = "echo first && echo second""#);
+
+    let mut cmd = worker.run(&[&uuid.to_string()]);
+    cmd.assert().success().stdout("first && echo second\n");
+}
+
+#[test]
+fn test_command_with_shell() {
+    let uuid = Uuid::new_v4();
+    let worker = WorkerTestConfig::with_project(
+        &uuid,
+        r#"command = "echo first && echo second"
+        shell = true"#,
+    );
+
+    let mut cmd = worker.run(&[&uuid.to_string()]);
+    cmd.env("SHELL", "sh")
+        .assert()
+        .success()
+        .stdout("first\nsecond\n");
+}
+
+#[test]
+fn test_command_with_named_shell() {
+    let uuid = Uuid::new_v4();
+    let worker = WorkerTestConfig::with_project(
+        &uuid,
+        r#"command = "echo $0"
+        shell = "sh""#,
+    );
+
+    let mut cmd = worker.run(&[&uuid.to_string()]);
+    cmd.assert().success().stdout("sh\n");
+}
+
+#[test]
+fn test_command_unterminated_quote() {
+    let uuid = Uuid::new_v4();
+    let worker = WorkerTestConfig::with_project(&uuid, r#"command = "echo 'unterminated""#);
+
+    let mut cmd = worker.run(&[&uuid.to_string()]);
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("Invalid command of project"));
+
+    let mut cmd = worker.cmd("config", &["validate"]);
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("has an invalid command"));
+}
+
+#[test]
+fn test_run_command_uses_user_shell() {
+    let uuid = Uuid::new_v4();
+    let worker = WorkerTestConfig::new();
+
+    let mut cmd = worker.run(&["-n", &uuid.to_string(), "-c", "echo $0"]);
+    cmd.env("SHELL", "sh").assert().success().stdout("sh\n");
+}
@@ -1,5 +1,8 @@
 #![allow(dead_code)]
-use std::fs::DirEntry;
+use std::{
+    fs::DirEntry,
+    time::{Duration, Instant},
+};
 
 use assert_cmd::{cargo::cargo_bin, Command};
 use serde::Deserialize;
@@ -7,6 +10,9 @@ use sysinfo::{Pid, System};
 use tempfile::TempDir;
 use uuid::Uuid;
 
+const WAIT_TIMEOUT: Duration = Duration::from_secs(5);
+const WAIT_INTERVAL: Duration = Duration::from_millis(50);
+
 #[derive(Debug, Deserialize)]
 pub struct Project {
     pub name: String,
@@ -45,6 +51,17 @@ pub struct WorkerTestConfig {
     groups: [Uuid; 2],
 }
 
+pub struct TestDaemon {
+    pub child: std::process::Child,
+}
+
+impl Drop for TestDaemon {
+    fn drop(&mut self) {
+        unsafe { libc::kill(self.child.id() as i32, libc::SIGTERM) };
+        let _ = self.child.wait();
+    }
+}
+
 impl Default for WorkerTestConfig {
     fn default() -> Self {
         Self::new()
@@ -53,6 +70,11 @@ impl Default for WorkerTestConfig {
 
 impl WorkerTestConfig {
     pub fn new() -> Self {
+        Self::with_projects("")
+    }
+
+    // Same as `new()`, but with additional projects appended to the config file
+    pub fn with_projects(extra: &str) -> Self {
         let dir = TempDir::with_prefix(Uuid::new_v4().to_string()).unwrap();
 
         let mock_path = cargo_bin("mock").to_string_lossy().to_string();
@@ -112,6 +134,8 @@ impl WorkerTestConfig {
             command = ["sh", "-c", "{cmd_echo}"]
             cwd = "/"
             dependencies = ["{name1}", "{name2}"]
+
+            {extra}
             "#
             ),
         )
@@ -125,9 +149,70 @@ impl WorkerTestConfig {
         }
     }
 
+    // Same as `with_projects()`, with one project called `name`. `fields` are the rest of its
+    // fields, written as in the config file
+    pub fn with_project(name: &Uuid, fields: &str) -> Self {
+        Self::with_projects(&format!(
+            r#"
+            [[project]]
+            name = "{name}"
+            {fields}
+            "#
+        ))
+    }
+
+    pub fn path(&self) -> &std::path::Path {
+        self.dir.path()
+    }
+
+    pub fn log(&self, name: &str) -> String {
+        std::fs::read_to_string(self.dir.path().join(".worker/log").join(name)).unwrap_or_default()
+    }
+
+    // Polls until `condition` holds, and fails the test if it doesn't within a few seconds
+    pub fn wait_until(&self, what: &str, condition: impl Fn() -> bool) {
+        let start = Instant::now();
+        while !condition() {
+            if start.elapsed() > WAIT_TIMEOUT {
+                panic!("Timed out waiting until {what}");
+            }
+            std::thread::sleep(WAIT_INTERVAL);
+        }
+    }
+
+    // Waits until the log of the project contains `expected`, and returns the log
+    pub fn wait_for_log(&self, name: &str, expected: &str) -> String {
+        self.wait_until(&format!("the log of {name} contains {expected:?}"), || {
+            self.log(name).contains(expected)
+        });
+        self.log(name)
+    }
+
+    // Runs `worker daemon` in the background until the returned guard is dropped
+    pub fn daemon(&self) -> TestDaemon {
+        let child = std::process::Command::new(cargo_bin("worker"))
+            .current_dir(&self.dir)
+            .arg("daemon")
+            .spawn()
+            .unwrap();
+
+        let socket = self.dir.path().join(".worker/worker.sock");
+        while !socket.exists() {
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+
+        TestDaemon { child }
+    }
+
+    pub fn cmd(&self, command: &str, args: &[&str]) -> Command {
+        self.run_cmd(command, Some(args))
+    }
+
     fn run_cmd(&self, command: &str, projects: Option<&[&str]>) -> Command {
         let mut cmd = Command::cargo_bin("worker").unwrap();
-        cmd.current_dir(&self.dir).arg(command);
+        // Commands given with `-c` run through `$SHELL`. Some shells replace themselves with the
+        // last command, which would hide the process from `pids`
+        cmd.current_dir(&self.dir).env("SHELL", "sh").arg(command);
 
         if let Some(projects) = projects {
             cmd.args(projects);
@@ -0,0 +1,133 @@
+use common::WorkerTestConfig;
+use predicates::prelude::predicate;
+
+mod common;
+
+fn with_config(content: &str) -> WorkerTestConfig {
+    let worker = WorkerTestConfig::new();
+    std::fs::write(worker.path().join(".worker.toml"), content).unwrap();
+    worker
+}
+
+#[test]
+fn test_config_validate_reports_mistakes() {
+    let worker = with_config(
+        r#"[[project]]
+name = "api"
+command = []
+cwd = "backend"
+dependencies = ["db", "cache"]
+
+[[project]]
+name = "api"
+command = ["true"]
+group = ["web"]
+
+[[project]]
+name = "web"
+command = ["true"]
+
+[[project]]
+name = "my-db"
+command = ["true"]
+"#,
+    );
+    let path = worker.path().join(".worker.toml");
+    let path = path.display();
+
+    let mut cmd = worker.cmd("config", &["validate"]);
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains(format!(
+            "{path}:3:11: error: Project api has an empty command\n"
+        )))
+        .stderr(predicate::str::contains(format!(
+            "{path}:4:7: error: The cwd of api is not a directory"
+        )))
+        .stderr(predicate::str::contains(format!(
+            "{path}:8:8: error: Project api is defined more than once\n"
+        )))
+        .stderr(predicate::str::contains(format!(
+            "{path}:17:8: warning: Project name my-db contains `-`"
+        )))
+        .stderr(predicate::str::contains(format!(
+            "{path}:10:10: error: Group web has the same name as a project\n"
+        )))
+        .stderr(predicate::str::contains("Found 4 errors in the config"));
+}
+
+#[test]
+fn test_config_validate_unknown_dependency_and_cycle() {
+    let worker = with_config(
+        r#"[[project]]
+name = "api"
+command = ["true"]
+dependencies = ["db"]
+"#,
+    );
+    let path = worker.path().join(".worker.toml");
+
+    let mut cmd = worker.cmd("config", &["validate"]);
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains(format!(
+            "{}:4:17: error: api depends on unknown project db\n",
+            path.display()
+        )));
+
+    let worker = with_config(
+        r#"[[project]]
+name = "api"
+command = ["true"]
+dependencies = ["db"]
+
+[[project]]
+name = "db"
+command = ["true"]
+dependencies = ["api"]
+"#,
+    );
+
+    let mut cmd = worker.cmd("config", &["validate"]);
+    cmd.assert().failure().stderr(predicate::str::contains(
+        "error: Dependency cycle between projects",
+    ));
+}
+
+#[test]
+fn test_config_validate_valid() {
+    let worker = WorkerTestConfig::new();
+
+    let mut cmd = worker.cmd("config", &["validate"]);
+    cmd.assert().success().stdout("The config is valid\n");
+}
+
+#[test]
+fn test_config_show() {
+    let worker = with_config(
+        r#"[env]
+SHARED = "yes"
+
+[[project]]
+name = "api"
+command = ["echo", "${project.name}"]
+cwd = "."
+"#,
+    );
+
+    let mut cmd = worker.cmd("config", &["show"]);
+    cmd.assert().success().stdout(format!(
+        r#"[env]
+SHARED = "yes"
+
+[[project]]
+name = "api"
+command = [
+    "echo",
+    "api",
+]
+cwd = "{}"
+"#,
+        worker.path().display()
+    ));
+}
@@ -0,0 +1,60 @@
+use common::WorkerTestConfig;
+use predicates::prelude::predicate;
+
+mod common;
+
+#[test]
+fn test_cwd_relative_to_config_dir() {
+    let worker = WorkerTestConfig::with_projects(
+        r#"
+        [[project]]
+        name = "relative"
+        command = ["pwd"]
+        cwd = "./backend"
+
+        [[project]]
+        name = "default"
+        command = ["pwd"]
+        "#,
+    );
+    std::fs::create_dir_all(worker.path().join("backend/src")).unwrap();
+    let config_dir = worker.path().canonicalize().unwrap();
+
+    // Run from another directory than the config file is in
+    let mut cmd = worker.run(&["relative"]);
+    cmd.current_dir(worker.path().join("backend/src"))
+        .assert()
+        .success()
+        .stdout(format!("{}\n", config_dir.join("backend").display()));
+
+    let mut cmd = worker.run(&["default"]);
+    cmd.current_dir(worker.path().join("backend/src"))
+        .assert()
+        .success()
+        .stdout(format!("{}\n", config_dir.display()));
+}
+
+#[test]
+fn test_cwd_does_not_exist() {
+    let worker = WorkerTestConfig::with_projects(
+        r#"
+        [[project]]
+        name = "missing"
+        command = ["pwd"]
+        cwd = "frontend"
+        "#,
+    );
+
+    // The rest of the config can still be used
+    let mut cmd = worker.list();
+    cmd.assert().success();
+
+    let mut cmd = worker.start(&["missing"]);
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains(format!(
+            "The cwd of missing is not a directory: {} (relative to {})",
+            worker.path().join("frontend").display(),
+            worker.path().display()
+        )));
+}
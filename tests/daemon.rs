@@ -0,0 +1,167 @@
+use std::{
+    io::{BufRead, BufReader},
+    process::Stdio,
+};
+
+use assert_cmd::cargo::cargo_bin;
+use common::{WorkerTestConfig, WorkerTestProject};
+use sysinfo::{Pid, System};
+use uuid::Uuid;
+
+use crate::common::PidError;
+
+mod common;
+
+#[test]
+fn test_daemon_owns_started_projects() {
+    let worker = WorkerTestConfig::new();
+    let daemon = worker.daemon();
+
+    let project_name = worker.project_name(&WorkerTestProject::One);
+
+    let mut cmd = worker.start(&[&project_name]);
+    cmd.assert().success();
+
+    let pids = worker.pids(&project_name).unwrap();
+    assert_eq!(pids.len(), 1);
+
+    // The command is run by the supervisor, which is a child of the daemon
+    let sys = System::new_all();
+    let supervisor = sys.process(pids[0]).unwrap().parent().unwrap();
+    assert_eq!(
+        sys.process(supervisor).unwrap().parent(),
+        Some(Pid::from_u32(daemon.child.id()))
+    );
+
+    let mut cmd = worker.cmd("status", &["--quiet"]);
+    cmd.assert().success().stdout(format!("{project_name}\n"));
+
+    let mut cmd = worker.stop(&[&project_name]);
+    cmd.assert().success();
+
+    assert_eq!(Err(PidError::FileNotFound), worker.pids(&project_name));
+}
+
+#[test]
+fn test_daemon_notices_exited_projects() {
+    let worker = WorkerTestConfig::new();
+    let _daemon = worker.daemon();
+
+    let project_name = worker.project_name(&WorkerTestProject::Four);
+
+    let mut cmd = worker.start(&[&project_name]);
+    cmd.assert().success();
+
+    worker.wait_until(&format!("{project_name} has exited"), || {
+        let output = worker.cmd("status", &["--quiet"]).output().unwrap();
+        output.status.success() && output.stdout.is_empty()
+    });
+
+    assert_eq!(Err(PidError::FileNotFound), worker.pids(&project_name));
+}
+
+#[test]
+fn test_daemon_adopts_running_projects() {
+    let worker = WorkerTestConfig::new();
+
+    let project_name = worker.project_name(&WorkerTestProject::One);
+
+    let mut cmd = worker.start(&[&project_name]);
+    cmd.assert().success();
+
+    let _daemon = worker.daemon();
+
+    let mut cmd = worker.cmd("status", &["--quiet"]);
+    cmd.assert().success().stdout(format!("{project_name}\n"));
+
+    let mut cmd = worker.restart(&[&project_name]);
+    cmd.assert().success();
+
+    let mut cmd = worker.cmd("status", &["--quiet"]);
+    cmd.assert().success().stdout(format!("{project_name}\n"));
+
+    let mut cmd = worker.stop(&[&project_name]);
+    cmd.assert().success();
+
+    assert_eq!(Err(PidError::FileNotFound), worker.pids(&project_name));
+}
+
+#[test]
+fn test_daemon_keeps_project_off_command_line() {
+    let uuid = Uuid::new_v4();
+    let secret = Uuid::new_v4().to_string();
+    let worker = WorkerTestConfig::with_project(
+        &uuid,
+        &format!(
+            r#"
+            command = ["sleep", "30"]
+            cwd = "/"
+            envs = {{ WORKER_TEST_SECRET = "{secret}" }}
+            "#
+        ),
+    );
+    let _daemon = worker.daemon();
+
+    let mut cmd = worker.start(&[&uuid.to_string()]);
+    cmd.assert().success();
+    assert_eq!(worker.pids(&uuid.to_string()).unwrap().len(), 1);
+
+    // The supervisor gets the project from the daemon without it showing up in e.g. `ps`
+    let sys = System::new_all();
+    assert!(!sys.processes().values().any(|p| p
+        .cmd()
+        .iter()
+        .any(|arg| arg.to_string_lossy().contains(&secret))));
+
+    let mut cmd = worker.stop(&[&uuid.to_string()]);
+    cmd.assert().success();
+}
+
+#[test]
+fn test_daemon_already_running() {
+    let worker = WorkerTestConfig::new();
+    let _daemon = worker.daemon();
+
+    let mut cmd = worker.cmd("daemon", &[]);
+    cmd.assert().failure();
+}
+
+#[test]
+fn test_daemon_serves_logs() {
+    let uuid = Uuid::new_v4();
+    let name = uuid.to_string();
+    // Keeps running until the test creates `go` in the config dir
+    let worker = WorkerTestConfig::with_project(
+        &uuid,
+        r#"
+        command = ["sh", "-c", "seq -f 'line %g' 1 100; until [ -e go ]; do sleep 0.1; done; echo done"]
+        "#,
+    );
+    let _daemon = worker.daemon();
+
+    let mut cmd = worker.start(&[&name]);
+    cmd.assert().success();
+
+    worker.wait_for_log(&name, "line 100\n");
+
+    let mut cmd = worker.logs(&[&name, "-n", "2"]);
+    cmd.assert().success().stdout("line 99\nline 100\n");
+
+    let mut cmd = worker.logs(&[&name, "-n", "1", "--grep", "^line 5.$"]);
+    cmd.assert().success().stdout("line 59\n");
+
+    // Follows the log file from where the daemon stopped, without repeating or skipping lines
+    let mut follow = std::process::Command::new(cargo_bin("worker"))
+        .current_dir(worker.path())
+        .args(["logs", &name, "-n", "1", "--follow"])
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let mut lines = BufReader::new(follow.stdout.take().unwrap()).lines();
+    assert_eq!(lines.next().unwrap().unwrap(), "line 100");
+
+    std::fs::write(worker.path().join("go"), "").unwrap();
+    assert_eq!(lines.next().unwrap().unwrap(), "done");
+    assert!(lines.next().is_none());
+    assert!(follow.wait().unwrap().success());
+}
@@ -0,0 +1,128 @@
+use common::WorkerTestConfig;
+use predicates::prelude::predicate;
+use tempfile::TempDir;
+use uuid::Uuid;
+
+mod common;
+
+// Prints the variables that are set in different places
+const PRINTING_ENV: &str = r#"command = ["sh", "-c", "echo $SHARED $FROM_FILE $OVERRIDE"]"#;
+
+const SHARED_ENV: &str = r#"
+[env]
+SHARED = "shared"
+FROM_FILE = "config"
+OVERRIDE = "config"
+"#;
+
+#[test]
+fn test_env_precedence() {
+    let uuid = Uuid::new_v4();
+    let cwd = TempDir::new().unwrap();
+    std::fs::write(
+        cwd.path().join(".env"),
+        "# Comment\nFROM_FILE=file\nexport OVERRIDE='file'\n",
+    )
+    .unwrap();
+
+    let worker = WorkerTestConfig::with_project(
+        &uuid,
+        &format!(
+            r#"
+            {PRINTING_ENV}
+            cwd = "{}"
+            env_file = ".env"
+            envs = {{ OVERRIDE = "envs" }}
+            {SHARED_ENV}
+            "#,
+            cwd.path().display()
+        ),
+    );
+
+    let mut cmd = worker.run(&[&uuid.to_string()]);
+    cmd.assert().success().stdout("shared file envs\n");
+}
+
+#[test]
+fn test_env_multiple_files() {
+    let uuid = Uuid::new_v4();
+    let cwd = TempDir::new().unwrap();
+    std::fs::write(cwd.path().join(".env"), "FROM_FILE=first\nOVERRIDE=first\n").unwrap();
+    std::fs::write(
+        cwd.path().join(".env.local"),
+        "OVERRIDE=\"second # not a comment\" # comment\n",
+    )
+    .unwrap();
+
+    let worker = WorkerTestConfig::with_project(
+        &uuid,
+        &format!(
+            r#"
+            {PRINTING_ENV}
+            cwd = "{}"
+            env_file = [".env", ".env.local"]
+            {SHARED_ENV}
+            "#,
+            cwd.path().display()
+        ),
+    );
+
+    let mut cmd = worker.run(&[&uuid.to_string()]);
+    cmd.assert()
+        .success()
+        .stdout("shared first second # not a comment\n");
+}
+
+#[test]
+fn test_env_file_missing() {
+    let uuid = Uuid::new_v4();
+    let cwd = TempDir::new().unwrap();
+
+    let worker = WorkerTestConfig::with_project(
+        &uuid,
+        &format!(
+            r#"
+            {PRINTING_ENV}
+            cwd = "{}"
+            env_file = ".env"
+            "#,
+            cwd.path().display()
+        ),
+    );
+
+    let mut cmd = worker.run(&[&uuid.to_string()]);
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("Couldn't read env file"));
+
+    let mut cmd = worker.start(&[&uuid.to_string()]);
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("Couldn't read env file"));
+    assert!(worker.log(&uuid.to_string()).is_empty());
+}
+
+#[test]
+fn test_env_for_started_project() {
+    let uuid = Uuid::new_v4();
+    let cwd = TempDir::new().unwrap();
+    std::fs::write(cwd.path().join(".env"), "FROM_FILE=file\n").unwrap();
+
+    let worker = WorkerTestConfig::with_project(
+        &uuid,
+        &format!(
+            r#"
+            {PRINTING_ENV}
+            cwd = "{}"
+            env_file = ".env"
+            {SHARED_ENV}
+            "#,
+            cwd.path().display()
+        ),
+    );
+
+    let mut cmd = worker.start(&[&uuid.to_string()]);
+    cmd.assert().success();
+
+    worker.wait_for_log(&uuid.to_string(), "shared file config\n");
+}
@@ -0,0 +1,126 @@
+use common::{WorkerTestConfig, WorkerTestProject};
+use predicates::prelude::predicate;
+use uuid::Uuid;
+
+mod common;
+
+#[test]
+fn test_graph_tree() {
+    let worker = WorkerTestConfig::new();
+
+    let project_name = worker.project_name(&WorkerTestProject::Five);
+    let dep1_name = worker.project_name(&WorkerTestProject::One);
+    let dep2_name = worker.project_name(&WorkerTestProject::Two);
+
+    let mut cmd = worker.cmd("graph", &[&project_name]);
+    cmd.assert().success().stdout(format!(
+        "{project_name}\n├── {dep1_name}\n└── {dep2_name}\n"
+    ));
+}
+
+#[test]
+fn test_graph_dot() {
+    let worker = WorkerTestConfig::new();
+
+    let project_name = worker.project_name(&WorkerTestProject::Five);
+    let dep1_name = worker.project_name(&WorkerTestProject::One);
+
+    let mut cmd = worker.cmd("graph", &["--dot"]);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::starts_with("digraph worker {\n"))
+        .stdout(predicate::str::contains(format!(
+            "\"{project_name}\" -> \"{dep1_name}\";"
+        )));
+}
+
+#[test]
+fn test_graph_cycle() {
+    let name1 = Uuid::new_v4();
+    let name2 = Uuid::new_v4();
+    let worker = WorkerTestConfig::with_projects(&format!(
+        r#"
+        [[project]]
+        name = "{name1}"
+        command = ["sh", "-c", "sleep 5"]
+        cwd = "/"
+        dependencies = ["{name2}"]
+
+        [[project]]
+        name = "{name2}"
+        command = ["sh", "-c", "sleep 5"]
+        cwd = "/"
+        dependencies = ["{name1}"]
+        "#
+    ));
+
+    let mut cmd = worker.list();
+    // The cycle is reported starting from the project sorted first
+    let (first, second) = if name1.to_string() < name2.to_string() {
+        (name1, name2)
+    } else {
+        (name2, name1)
+    };
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains(format!(
+            "Dependency cycle between projects: {first} -> {second} -> {first}"
+        )));
+}
+
+#[test]
+fn test_graph_unknown_dependency() {
+    let name = Uuid::new_v4();
+    let worker = WorkerTestConfig::with_projects(&format!(
+        r#"
+        [[project]]
+        name = "{name}"
+        command = ["sh", "-c", "sleep 5"]
+        cwd = "/"
+        dependencies = ["unknown"]
+        "#
+    ));
+
+    let mut cmd = worker.list();
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains(format!(
+            "{name} depends on unknown project unknown"
+        )));
+}
+
+#[test]
+fn test_start_transitive_dependencies() {
+    let [name1, name2, name3] = [Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4()];
+    let worker = WorkerTestConfig::with_projects(&format!(
+        r#"
+        [[project]]
+        name = "{name1}"
+        command = ["sh", "-c", "echo {name1}; sleep 5"]
+        cwd = "/"
+        dependencies = ["{name2}"]
+
+        [[project]]
+        name = "{name2}"
+        command = ["sh", "-c", "echo {name2}; sleep 5"]
+        cwd = "/"
+        dependencies = ["{name3}"]
+
+        [[project]]
+        name = "{name3}"
+        command = ["sh", "-c", "echo {name3}; sleep 5"]
+        cwd = "/"
+        "#
+    ));
+
+    let mut cmd = worker.start(&[&name1.to_string()]);
+    cmd.assert().success();
+
+    let mut cmd = worker.status();
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains(name1.to_string()))
+        .stdout(predicate::str::contains(name2.to_string()))
+        .stdout(predicate::str::contains(name3.to_string()));
+}
@@ -0,0 +1,112 @@
+use common::{WorkerTestConfig, WorkerTestProject};
+use predicates::prelude::predicate;
+use uuid::Uuid;
+
+mod common;
+
+const CRASHING: &str = r#"
+    command = ["sh", "-c", "exit 101"]
+    cwd = "/"
+"#;
+
+// Waits until the run of the project is recorded, and returns the history
+fn wait_for_history(worker: &WorkerTestConfig, name: &str) -> String {
+    let history = || {
+        let output = worker.cmd("history", &[name]).output().unwrap().stdout;
+        String::from_utf8(output).unwrap()
+    };
+    worker.wait_until(&format!("a run of {name} is recorded"), || {
+        !history().is_empty()
+    });
+    history()
+}
+
+#[test]
+fn test_history_records_exit_code() {
+    let name = Uuid::new_v4();
+    let worker = WorkerTestConfig::with_project(&name, CRASHING);
+
+    let mut cmd = worker.start(&[&name.to_string()]);
+    cmd.assert().success();
+
+    assert!(wait_for_history(&worker, &name.to_string()).contains("exited (code 101)"));
+}
+
+#[test]
+fn test_history_records_stop() {
+    let worker = WorkerTestConfig::new();
+    let project_name = worker.project_name(&WorkerTestProject::One);
+
+    let mut cmd = worker.start(&[&project_name]);
+    cmd.assert().success();
+
+    let mut cmd = worker.stop(&[&project_name]);
+    cmd.assert().success();
+
+    let mut cmd = worker.cmd("history", &[&project_name]);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("stopped"));
+}
+
+#[test]
+fn test_history_records_killed_project() {
+    let uuid = Uuid::new_v4();
+    let name = uuid.to_string();
+    let worker = WorkerTestConfig::with_project(
+        &uuid,
+        r#"
+        command = ["sh", "-c", "trap '' INT; while true; do sleep 0.1; done"]
+        cwd = "/"
+        stop_timeout = 1
+        "#,
+    );
+
+    // Killed right away
+    let mut cmd = worker.start(&[&name]);
+    cmd.assert().success();
+    let mut cmd = worker.stop(&["--force", &name]);
+    cmd.assert().success();
+
+    // Killed after ignoring the stop signal
+    let mut cmd = worker.start(&[&name]);
+    cmd.assert().success();
+    let mut cmd = worker.stop(&[&name]);
+    cmd.assert().success();
+
+    let mut cmd = worker.cmd("history", &[&name]);
+    let output = cmd.assert().success().get_output().stdout.clone();
+    let output = String::from_utf8(output).unwrap();
+    assert_eq!(output.matches("stopped").count(), 2);
+}
+
+#[test]
+fn test_history_no_runs() {
+    let worker = WorkerTestConfig::new();
+    let project_name = worker.project_name(&WorkerTestProject::One);
+
+    let mut cmd = worker.cmd("history", &[&project_name]);
+    cmd.assert().failure();
+}
+
+#[test]
+fn test_status_all_shows_exited_projects() {
+    let name = Uuid::new_v4();
+    let worker = WorkerTestConfig::with_project(&name, CRASHING);
+    let project_name = worker.project_name(&WorkerTestProject::One);
+
+    let mut cmd = worker.start(&[&name.to_string()]);
+    cmd.assert().success();
+
+    wait_for_history(&worker, &name.to_string());
+
+    let mut cmd = worker.cmd("status", &["--all"]);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains(format!(
+            "{name} exited (code 101) "
+        )))
+        .stdout(predicate::str::contains(format!(
+            "{project_name} is not running"
+        )));
+}
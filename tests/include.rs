@@ -0,0 +1,130 @@
+use common::WorkerTestConfig;
+use tempfile::TempDir;
+
+mod common;
+
+fn write(worker: &WorkerTestConfig, path: &str, content: &str) {
+    let path = worker.path().join(path);
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+    std::fs::write(path, content).unwrap();
+}
+
+#[test]
+fn test_include_service_configs() {
+    let worker = WorkerTestConfig::new();
+    write(
+        &worker,
+        ".worker.toml",
+        r#"
+        include = ["backend", "frontend/.worker.toml"]
+
+        [[project]]
+        name = "db"
+        command = ["pwd"]
+        "#,
+    );
+    write(
+        &worker,
+        "backend/.worker.toml",
+        r#"
+        [[project]]
+        name = "api"
+        command = ["pwd"]
+        "#,
+    );
+    write(
+        &worker,
+        "frontend/.worker.toml",
+        r#"
+        [[project]]
+        name = "web"
+        command = ["pwd"]
+        cwd = "src"
+        "#,
+    );
+    std::fs::create_dir(worker.path().join("frontend/src")).unwrap();
+
+    let mut cmd = worker.cmd("list", &["--quiet"]);
+    cmd.assert().success().stdout("api\nweb\ndb\n");
+
+    // Paths are relative to the file the project is defined in
+    let mut cmd = worker.run(&["api"]);
+    cmd.assert()
+        .success()
+        .stdout(format!("{}\n", worker.path().join("backend").display()));
+
+    let mut cmd = worker.run(&["web"]);
+    cmd.assert().success().stdout(format!(
+        "{}\n",
+        worker.path().join("frontend/src").display()
+    ));
+}
+
+#[test]
+fn test_inherit_parent_and_user_config() {
+    let worker = WorkerTestConfig::new();
+    let config_home = TempDir::new().unwrap();
+    write(
+        &worker,
+        ".worker.toml",
+        r#"
+        inherit = true
+
+        [[project]]
+        name = "db"
+        command = ["sleep", "5"]
+
+        [[project]]
+        name = "api"
+        command = ["echo", "root"]
+        "#,
+    );
+    write(
+        &worker,
+        "backend/.worker.toml",
+        r#"
+        inherit = true
+
+        [[project]]
+        name = "api"
+        command = ["echo", "backend"]
+        "#,
+    );
+    std::fs::create_dir(config_home.path().join("worker")).unwrap();
+    std::fs::write(
+        config_home.path().join("worker/config.toml"),
+        r#"
+        [[project]]
+        name = "editor"
+        command = ["true"]
+        cwd = "/"
+        "#,
+    )
+    .unwrap();
+
+    let mut cmd = worker.cmd("list", &["--quiet"]);
+    cmd.current_dir(worker.path().join("backend"))
+        .env("XDG_CONFIG_HOME", config_home.path())
+        .assert()
+        .success()
+        .stdout("editor\ndb\napi\n");
+
+    // The nearest config overrides the project with the same name
+    let mut cmd = worker.run(&["api"]);
+    cmd.current_dir(worker.path().join("backend"))
+        .assert()
+        .success()
+        .stdout("backend\n");
+
+    // Projects started from a service are seen from the root
+    let mut cmd = worker.start(&["db"]);
+    cmd.current_dir(worker.path().join("backend"))
+        .assert()
+        .success();
+
+    let mut cmd = worker.cmd("status", &["--quiet"]);
+    cmd.assert().success().stdout("db\n");
+
+    let mut cmd = worker.stop(&["db"]);
+    cmd.assert().success();
+}
@@ -0,0 +1,147 @@
+use common::WorkerTestConfig;
+use predicates::prelude::predicate;
+
+mod common;
+
+#[test]
+fn test_interpolate_builtins_and_env() {
+    let worker = WorkerTestConfig::with_projects(
+        r#"
+        [[project]]
+        name = "interpolated"
+        command = ["sh", "-c", "echo ${project.name} $GREETING $${HOME:+set} $(basename $PWD)"]
+        cwd = "${config_dir}/${SUBDIR:-sub}"
+        envs = { GREETING = "${WORKER_TEST_GREETING}" }
+        "#,
+    );
+    std::fs::create_dir(worker.path().join("sub")).unwrap();
+
+    let mut cmd = worker.run(&["interpolated"]);
+    cmd.env("WORKER_TEST_GREETING", "hello")
+        .assert()
+        .success()
+        .stdout("interpolated hello set sub\n");
+}
+
+#[test]
+fn test_interpolate_undefined_variable() {
+    let worker = WorkerTestConfig::with_projects(
+        r#"
+        [[project]]
+        name = "interpolated"
+        command = ["echo", "${WORKER_TEST_UNDEFINED}"]
+        cwd = "/"
+        "#,
+    );
+
+    let mut cmd = worker.run(&["interpolated"]);
+    cmd.assert().failure().stderr(predicate::str::contains(
+        "Variable WORKER_TEST_UNDEFINED is not set",
+    ));
+
+    let mut cmd = worker.cmd("config", &["validate"]);
+    cmd.assert().failure().stderr(predicate::str::contains(
+        "Variable WORKER_TEST_UNDEFINED is not set",
+    ));
+}
+
+#[test]
+fn test_interpolate_undefined_variable_only_fails_its_project() {
+    let worker = WorkerTestConfig::with_projects(
+        r#"
+        [[project]]
+        name = "broken"
+        command = ["echo", "${WORKER_TEST_UNDEFINED}"]
+        cwd = "/"
+
+        [[project]]
+        name = "working"
+        command = ["echo", "works"]
+        cwd = "/"
+        "#,
+    );
+
+    let mut cmd = worker.list();
+    cmd.assert().success();
+
+    let mut cmd = worker.run(&["working"]);
+    cmd.assert().success().stdout("works\n");
+}
+
+#[test]
+fn test_interpolate_envs_before_environment() {
+    let worker = WorkerTestConfig::with_projects(
+        r#"
+        [env]
+        HOST = "localhost"
+        PORT = "8080"
+
+        [[project]]
+        name = "interpolated"
+        command = ["echo", "${HOST}:${PORT}"]
+        cwd = "/"
+        envs = { PORT = "3000" }
+        "#,
+    );
+
+    let mut cmd = worker.run(&["interpolated"]);
+    cmd.env("PORT", "1234")
+        .assert()
+        .success()
+        .stdout("localhost:3000\n");
+}
+
+#[test]
+fn test_interpolate_after_splitting() {
+    let worker = WorkerTestConfig::with_projects(
+        r#"
+        [[project]]
+        name = "interpolated"
+        command = "printf '%s|' ${GREETING} next"
+        cwd = "/"
+        envs = { GREETING = "hello 'big' world" }
+        "#,
+    );
+
+    let mut cmd = worker.run(&["interpolated"]);
+    cmd.assert().success().stdout("hello 'big' world|next|");
+}
+
+#[test]
+fn test_interpolate_keeps_unset_variables_for_the_shell() {
+    let worker = WorkerTestConfig::with_projects(
+        r#"
+        [[project]]
+        name = "interpolated"
+        command = "echo ${project.name} ${WORKER_TEST_FROM_FILE}"
+        shell = "sh"
+        cwd = "${config_dir}"
+        env_file = ".env"
+        "#,
+    );
+    std::fs::write(worker.path().join(".env"), "WORKER_TEST_FROM_FILE=hello\n").unwrap();
+
+    let mut cmd = worker.run(&["interpolated"]);
+    cmd.assert().success().stdout("interpolated hello\n");
+}
+
+#[test]
+fn test_interpolate_quotes_values_for_the_shell() {
+    let worker = WorkerTestConfig::with_projects(
+        r#"
+        [env]
+        MESSAGE = "hello  $HOME; echo ran"
+
+        [[project]]
+        name = "interpolated"
+        command = "printf '%s|' ${MESSAGE} ${MISSING:-} next"
+        shell = "sh"
+        cwd = "${config_dir}"
+        "#,
+    );
+
+    let mut cmd = worker.run(&["interpolated"]);
+    cmd.assert()
+        .success()
+        .stdout("hello  $HOME; echo ran|next|");
+}
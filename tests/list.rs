@@ -18,3 +18,36 @@ fn test_list_projects() {
         .stdout(predicate::str::contains(project2_name))
         .stdout(predicate::str::contains(project3_name));
 }
+
+#[test]
+fn test_list_format_json() {
+    let worker = WorkerTestConfig::new();
+    let project1_name = worker.project_name(&WorkerTestProject::One);
+    let project5_name = worker.project_name(&WorkerTestProject::Five);
+
+    let mut cmd = worker.start(&[&project1_name]);
+    cmd.assert().success();
+
+    let mut cmd = worker.cmd("list", &["--format", "json"]);
+    cmd.assert().success();
+
+    let output = cmd.output().unwrap().stdout;
+    let json: serde_json::Value = serde_json::from_slice(&output).unwrap();
+    let projects = json.as_array().unwrap();
+
+    assert_eq!(projects.len(), 6);
+
+    let project1 = projects
+        .iter()
+        .find(|p| p["name"] == project1_name.as_str());
+    assert!(project1.unwrap()["pid"].is_i64());
+
+    let project5 = projects
+        .iter()
+        .find(|p| p["name"] == project5_name.as_str());
+    assert!(project5.unwrap()["pid"].is_null());
+    assert_eq!(
+        project5.unwrap()["dependencies"].as_array().unwrap().len(),
+        2
+    );
+}
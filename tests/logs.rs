@@ -1,5 +1,10 @@
-use std::time::{Duration, Instant};
+use std::{
+    io::{BufRead, BufReader},
+    process::Stdio,
+    time::{Duration, Instant, SystemTime},
+};
 
+use assert_cmd::cargo::cargo_bin;
 use common::{WorkerTestConfig, WorkerTestProject};
 use uuid::Uuid;
 
@@ -68,3 +73,315 @@ fn test_logs_command_success() {
     }
     unreachable!("Couldn't find output in 1 second")
 }
+
+#[test]
+fn test_logs_without_timestamps() {
+    let worker = WorkerTestConfig::new();
+
+    let project_name = worker.project_name(&WorkerTestProject::One);
+
+    let mut cmd = worker.start(&[&project_name]);
+    cmd.assert().success();
+
+    worker.wait_for_log(&project_name, "Hello from mock!");
+
+    // The log file only has the stream in front of each line
+    assert_eq!(worker.log(&project_name), "out Hello from mock!\n");
+
+    let mut cmd = worker.logs(&[&project_name, "--timestamps"]);
+    cmd.assert().failure();
+
+    let mut cmd = worker.logs(&[&project_name, "--stdout"]);
+    cmd.assert().success().stdout("Hello from mock!\n");
+
+    let mut cmd = worker.logs(&[&project_name, "--stderr"]);
+    cmd.assert().success().stdout("");
+}
+
+#[test]
+fn test_logs_timestamps() {
+    let uuid = Uuid::new_v4();
+    let worker = WorkerTestConfig::with_project(
+        &uuid,
+        r#"
+        command = ["sh", "-c", "echo hello; sleep 5"]
+        cwd = "/"
+        log = { timestamps = true }
+        "#,
+    );
+
+    let mut cmd = worker.start(&[&uuid.to_string()]);
+    cmd.assert().success();
+
+    let log = worker.wait_for_log(&uuid.to_string(), "hello\n");
+    let (timestamp, content) = log.split_once(' ').unwrap();
+    assert!(humantime::parse_rfc3339(timestamp).is_ok());
+    assert_eq!(content, "out hello\n");
+
+    let mut cmd = worker.logs(&[&uuid.to_string()]);
+    cmd.assert().success().stdout("hello\n");
+
+    let mut cmd = worker.logs(&[&uuid.to_string(), "--timestamps"]);
+    cmd.assert().success().stdout(log.replace(" out ", " "));
+}
+
+#[test]
+fn test_logs_rotated_on_restart() {
+    let worker = WorkerTestConfig::new();
+
+    let project_name = worker.project_name(&WorkerTestProject::One);
+
+    let mut cmd = worker.start(&[&project_name]);
+    cmd.assert().success();
+
+    let mut cmd = worker.restart(&[&project_name]);
+    cmd.assert().success();
+
+    let previous = worker.path().join(format!(".worker/log/{project_name}.1"));
+    assert!(std::fs::read_to_string(previous)
+        .unwrap()
+        .contains("Hello from mock!"));
+}
+
+#[test]
+fn test_logs_rotated_by_size() {
+    let uuid = Uuid::new_v4();
+    let worker = WorkerTestConfig::with_project(
+        &uuid,
+        r#"
+        command = ["sh", "-c", "for i in $(seq 1 300); do echo line $i; done; sleep 5"]
+        cwd = "/"
+        log = { max_size = "1K", keep = 2 }
+        "#,
+    );
+
+    let mut cmd = worker.start(&[&uuid.to_string()]);
+    cmd.assert().success();
+
+    worker.wait_for_log(&uuid.to_string(), "line 300\n");
+
+    let log_dir = worker.path().join(".worker/log");
+    assert!(log_dir.join(format!("{uuid}.1")).exists());
+    assert!(log_dir.join(format!("{uuid}.2")).exists());
+    assert!(!log_dir.join(format!("{uuid}.3")).exists());
+
+    let size = std::fs::metadata(log_dir.join(format!("{uuid}.1")))
+        .unwrap()
+        .len();
+    assert!((1024..1200).contains(&size));
+}
+
+#[test]
+fn test_logs_stdout_and_stderr() {
+    let uuid = Uuid::new_v4();
+    let worker = WorkerTestConfig::with_project(
+        &uuid,
+        r#"
+        command = ["sh", "-c", "echo to stdout; sleep 0.1; echo to stderr >&2; sleep 5"]
+        cwd = "/"
+        "#,
+    );
+
+    let mut cmd = worker.start(&[&uuid.to_string()]);
+    cmd.assert().success();
+
+    worker.wait_for_log(&uuid.to_string(), "to stderr\n");
+
+    let mut cmd = worker.logs(&[&uuid.to_string()]);
+    cmd.assert().success().stdout("to stdout\nto stderr\n");
+
+    let mut cmd = worker.logs(&[&uuid.to_string(), "--stdout"]);
+    cmd.assert().success().stdout("to stdout\n");
+
+    let mut cmd = worker.logs(&[&uuid.to_string(), "--stderr"]);
+    cmd.assert().success().stdout("to stderr\n");
+}
+
+#[test]
+fn test_logs_follow_across_restart() {
+    let uuid = Uuid::new_v4();
+    let worker = WorkerTestConfig::with_project(
+        &uuid,
+        r#"
+        command = ["sh", "-c", "echo hello; sleep 1"]
+        cwd = "/"
+        "#,
+    );
+
+    let mut cmd = worker.start(&[&uuid.to_string()]);
+    cmd.assert().success();
+
+    let mut follow = std::process::Command::new(cargo_bin("worker"))
+        .current_dir(worker.path())
+        .args(["logs", &uuid.to_string(), "-f"])
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let mut lines = BufReader::new(follow.stdout.take().unwrap()).lines();
+    assert_eq!(lines.next().unwrap().unwrap(), "hello");
+
+    let mut cmd = worker.restart(&[&uuid.to_string()]);
+    cmd.assert().success();
+
+    // Keeps following the new log after the restart, and exits once the project has stopped
+    assert_eq!(lines.next().unwrap().unwrap(), "hello");
+    assert!(lines.next().is_none());
+    assert!(follow.wait().unwrap().success());
+}
+
+#[test]
+fn test_logs_multiple_projects() {
+    let worker = WorkerTestConfig::with_projects(
+        r#"
+        [[project]]
+        name = "first"
+        command = ["sh", "-c", "echo one; sleep 0.2; echo three; sleep 5"]
+        cwd = "/"
+        group = ["both"]
+        log = { timestamps = true }
+
+        [[project]]
+        name = "second-project"
+        command = ["sh", "-c", "sleep 0.1; echo two; sleep 5"]
+        cwd = "/"
+        group = ["both"]
+        log = { timestamps = true }
+        "#,
+    );
+
+    let mut cmd = worker.start(&["both"]);
+    cmd.assert().success();
+
+    worker.wait_for_log("first", "three");
+    worker.wait_for_log("second-project", "two");
+
+    let mut cmd = worker.logs(&["both"]);
+    cmd.assert()
+        .success()
+        .stdout("first          | one\nsecond-project | two\nfirst          | three\n");
+
+    let mut cmd = worker.logs(&["first", "second-project", "-n", "1"]);
+    cmd.assert()
+        .success()
+        .stdout("second-project | two\nfirst          | three\n");
+
+    let mut cmd = worker.stop(&["both"]);
+    cmd.assert().success();
+}
+
+#[test]
+fn test_logs_grep() {
+    let uuid = Uuid::new_v4();
+    let worker = WorkerTestConfig::with_project(
+        &uuid,
+        r#"
+        command = ["sh", "-c", "echo GET /users; echo POST /users; echo GET /items; sleep 5"]
+        cwd = "/"
+        "#,
+    );
+
+    let mut cmd = worker.start(&[&uuid.to_string()]);
+    cmd.assert().success();
+
+    worker.wait_for_log(&uuid.to_string(), "GET /items");
+
+    let mut cmd = worker.logs(&[&uuid.to_string(), "--grep", "^GET"]);
+    cmd.assert().success().stdout("GET /users\nGET /items\n");
+
+    let mut cmd = worker.logs(&[&uuid.to_string(), "--grep", "^GET", "--invert"]);
+    cmd.assert().success().stdout("POST /users\n");
+
+    let mut cmd = worker.logs(&[&uuid.to_string(), "--grep", "("]);
+    cmd.assert().failure();
+}
+
+#[test]
+fn test_logs_since_and_until() {
+    let uuid = Uuid::new_v4();
+    let worker = WorkerTestConfig::with_project(
+        &uuid,
+        r#"
+        command = ["sh", "-c", "echo before; sleep 2; echo after; sleep 5"]
+        cwd = "/"
+        log = { timestamps = true }
+        "#,
+    );
+
+    let mut cmd = worker.start(&[&uuid.to_string()]);
+    cmd.assert().success();
+
+    worker.wait_for_log(&uuid.to_string(), "after");
+
+    let mut cmd = worker.logs(&[&uuid.to_string(), "--since", "1s"]);
+    cmd.assert().success().stdout("after\n");
+
+    let mut cmd = worker.logs(&[&uuid.to_string(), "--until", "1s"]);
+    cmd.assert().success().stdout("before\n");
+
+    let since = humantime::format_rfc3339(SystemTime::now() - Duration::from_secs(60)).to_string();
+    let mut cmd = worker.logs(&[&uuid.to_string(), "--since", &since]);
+    cmd.assert().success().stdout("before\nafter\n");
+
+    let mut cmd = worker.logs(&[&uuid.to_string(), "--since", "yesterday"]);
+    cmd.assert().failure();
+}
+
+#[test]
+fn test_logs_keep_output_that_looks_like_a_prefix() {
+    let uuid = Uuid::new_v4();
+    let worker = WorkerTestConfig::with_project(
+        &uuid,
+        r#"
+        command = ["sh", "-c", "echo '2024-04-20T12:34:56.789Z out GET /users'; echo done; sleep 5"]
+        cwd = "/"
+        "#,
+    );
+
+    let mut cmd = worker.start(&[&uuid.to_string()]);
+    cmd.assert().success();
+
+    worker.wait_for_log(&uuid.to_string(), "done");
+
+    let mut cmd = worker.logs(&[&uuid.to_string()]);
+    cmd.assert()
+        .success()
+        .stdout("2024-04-20T12:34:56.789Z out GET /users\ndone\n");
+
+    let mut cmd = worker.stop(&[&uuid.to_string()]);
+    cmd.assert().success();
+}
+
+#[test]
+fn test_logs_last_lines_of_long_log() {
+    let uuid = Uuid::new_v4();
+    let worker = WorkerTestConfig::with_project(
+        &uuid,
+        r#"
+        command = ["sh", "-c", "seq -f 'line %g' 1 5000; sleep 5"]
+        cwd = "/"
+        "#,
+    );
+
+    let mut cmd = worker.start(&[&uuid.to_string()]);
+    cmd.assert().success();
+
+    worker.wait_for_log(&uuid.to_string(), "line 5000\n");
+
+    let mut cmd = worker.logs(&[&uuid.to_string(), "-n", "3"]);
+    cmd.assert()
+        .success()
+        .stdout("line 4998\nline 4999\nline 5000\n");
+
+    // The lines are counted after filtering, even when they are further back than the last ones
+    let mut cmd = worker.logs(&[&uuid.to_string(), "-n", "2", "--grep", "^line 1.?.?$"]);
+    cmd.assert().success().stdout("line 198\nline 199\n");
+
+    let mut cmd = worker.logs(&[&uuid.to_string(), "-n", "10000"]);
+    let output = cmd.assert().success().get_output().stdout.clone();
+    let output = String::from_utf8(output).unwrap();
+    assert_eq!(output.lines().count(), 5000);
+    assert!(output.starts_with("line 1\nline 2\n"));
+
+    let mut cmd = worker.stop(&[&uuid.to_string()]);
+    cmd.assert().success();
+}
@@ -0,0 +1,49 @@
+use common::WorkerTestConfig;
+use uuid::Uuid;
+
+mod common;
+
+// Prints whether stdout and stderr are a terminal
+const CHECKING_TERMINAL: &str = r#"
+    command = ["sh", "-c", "if [ -t 1 ] && [ -t 2 ]; then echo terminal; else echo file; fi; echo done >&2"]
+    log = { timestamps = true }
+"#;
+
+// The content of the log lines without the timestamps, once both lines are written
+fn logged(worker: &WorkerTestConfig, name: &Uuid) -> Vec<String> {
+    worker.wait_until("both lines are logged", || {
+        worker.log(&name.to_string()).lines().count() == 2
+    });
+
+    worker
+        .log(&name.to_string())
+        .lines()
+        .map(|line| line.split_once(' ').unwrap().1.to_string())
+        .collect()
+}
+
+#[test]
+fn test_pty_command_writes_to_terminal() {
+    let uuid = Uuid::new_v4();
+    let worker = WorkerTestConfig::with_project(&uuid, &format!("{CHECKING_TERMINAL}\npty = true"));
+
+    let mut cmd = worker.start(&[&uuid.to_string()]);
+    cmd.assert().success();
+
+    // Both streams go to the terminal, and the lines don't end with `\r`
+    assert_eq!(logged(&worker, &uuid), ["out terminal", "out done"]);
+}
+
+#[test]
+fn test_without_pty_command_writes_to_pipes() {
+    let uuid = Uuid::new_v4();
+    let worker = WorkerTestConfig::with_project(&uuid, CHECKING_TERMINAL);
+
+    let mut cmd = worker.start(&[&uuid.to_string()]);
+    cmd.assert().success();
+
+    // The pipes are read separately, so the order of the streams isn't known
+    let mut lines = logged(&worker, &uuid);
+    lines.sort();
+    assert_eq!(lines, ["err done", "out file"]);
+}
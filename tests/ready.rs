@@ -0,0 +1,130 @@
+use std::{
+    io::{Read, Write},
+    net::TcpListener,
+    time::{Duration, Instant},
+};
+
+use common::WorkerTestConfig;
+use predicates::prelude::predicate;
+use uuid::Uuid;
+
+mod common;
+
+fn with_dependency(ready: &str, dep_cmd: &str) -> (WorkerTestConfig, String, String) {
+    let dep = Uuid::new_v4().to_string();
+    let project = Uuid::new_v4().to_string();
+
+    let worker = WorkerTestConfig::with_projects(&format!(
+        r#"
+        [[project]]
+        name = "{dep}"
+        command = ["sh", "-c", "{dep_cmd}"]
+        cwd = "/"
+        ready = {ready}
+
+        [[project]]
+        name = "{project}"
+        command = ["sh", "-c", "echo started; sleep 5"]
+        cwd = "/"
+        dependencies = ["{dep}"]
+        "#
+    ));
+
+    (worker, dep, project)
+}
+
+#[test]
+fn test_ready_log_waits_for_dependency() {
+    let (worker, dep, project) = with_dependency(
+        r#"{ log = "^db is (ready|up)$" }"#,
+        "sleep 1; echo db is ready; sleep 5",
+    );
+
+    let start = Instant::now();
+    let mut cmd = worker.start(&[&project]);
+    cmd.assert().success();
+
+    assert!(start.elapsed() >= Duration::from_secs(1));
+    assert!(worker.log(&dep).contains("db is ready"));
+}
+
+#[test]
+fn test_ready_timeout() {
+    let (worker, _, project) = with_dependency(
+        r#"{ log = "never printed", timeout = 1 }"#,
+        "echo starting; sleep 5",
+    );
+
+    let mut cmd = worker.start(&[&project]);
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("was not ready within 1s"));
+}
+
+#[test]
+fn test_ready_log_ignores_previous_run() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let marker = dir.path().join("started");
+    // Only the first run says it is ready
+    let (worker, dep, project) = with_dependency(
+        r#"{ log = "^db is ready$", timeout = 1 }"#,
+        &format!(
+            "if [ -e {0} ]; then echo db is starting; else touch {0}; echo db is ready; fi; sleep 5",
+            marker.display()
+        ),
+    );
+
+    let mut cmd = worker.start(&[&project]);
+    cmd.assert().success();
+
+    let mut cmd = worker.stop(&[&project, &dep]);
+    cmd.assert().success();
+
+    let mut cmd = worker.start(&[&project]);
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("was not ready within 1s"));
+}
+
+#[test]
+fn test_ready_port() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+
+    let (worker, _, project) = with_dependency(&format!("{{ port = {port} }}"), "sleep 5");
+
+    let mut cmd = worker.start(&[&project]);
+    cmd.assert().success();
+}
+
+#[test]
+fn test_ready_http() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            let _ = stream.read(&mut [0; 1024]);
+            let _ = stream.write_all(b"HTTP/1.1 204 No Content\r\n\r\n");
+        }
+    });
+
+    let (worker, _, project) = with_dependency(
+        &format!(r#"{{ http = "http://127.0.0.1:{port}/health", timeout = 2 }}"#),
+        "sleep 5",
+    );
+
+    let mut cmd = worker.start(&[&project]);
+    cmd.assert().success();
+}
+
+#[test]
+fn test_ready_cmd() {
+    let (worker, _, project) = with_dependency(r#"{ cmd = "false", timeout = 1 }"#, "sleep 5");
+
+    let mut cmd = worker.start(&[&project]);
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("was not ready within 1s"));
+}
@@ -227,3 +227,25 @@ fn test_restart_command_success() {
     let new_pid = worker.pids(&uuid.to_string()).unwrap()[0];
     assert_ne!(pid, new_pid);
 }
+
+#[test]
+fn test_restart_cascade_restarts_dependents() {
+    let worker = WorkerTestConfig::new();
+    let project_name = worker.project_name(&WorkerTestProject::Five);
+    let dep1_name = worker.project_name(&WorkerTestProject::One);
+    let dep2_name = worker.project_name(&WorkerTestProject::Two);
+
+    let mut cmd = worker.start(&[&project_name]);
+    cmd.assert().success();
+
+    let pid = worker.pids(&project_name).unwrap()[0];
+    let dep1_pid = worker.pids(&dep1_name).unwrap()[0];
+    let dep2_pid = worker.pids(&dep2_name).unwrap()[0];
+
+    let mut cmd = worker.restart(&["--cascade", &dep1_name]);
+    cmd.assert().success();
+
+    assert_ne!(pid, worker.pids(&project_name).unwrap()[0]);
+    assert_ne!(dep1_pid, worker.pids(&dep1_name).unwrap()[0]);
+    assert_eq!(dep2_pid, worker.pids(&dep2_name).unwrap()[0]);
+}
@@ -0,0 +1,93 @@
+use common::WorkerTestConfig;
+use predicates::prelude::{predicate, PredicateBooleanExt};
+use uuid::Uuid;
+
+mod common;
+
+fn is_running(worker: &WorkerTestConfig, name: &str) -> bool {
+    let output = worker.status().output().unwrap();
+    String::from_utf8_lossy(&output.stdout).contains(name)
+}
+
+#[test]
+fn test_restart_policy_on_failure_gives_up() {
+    let uuid = Uuid::new_v4();
+    let name = uuid.to_string();
+    let worker = WorkerTestConfig::with_project(
+        &uuid,
+        r#"
+        command = ["sh", "-c", "echo crashed; exit 1"]
+        cwd = "/"
+        restart = "on-failure"
+        max_retries = 2
+        "#,
+    );
+
+    let mut cmd = worker.start(&[&name]);
+    cmd.assert().success();
+
+    // Backoff is 1 and then 2 seconds before it gives up
+    let log = worker.wait_for_log(&name, "giving up after 2 retries");
+    assert_eq!(log.matches("crashed").count(), 3);
+
+    worker.wait_until("the project has exited", || !is_running(&worker, &name));
+}
+
+#[test]
+fn test_restart_policy_always_restarts_until_stopped() {
+    let uuid = Uuid::new_v4();
+    let name = uuid.to_string();
+    let worker = WorkerTestConfig::with_project(
+        &uuid,
+        r#"
+        command = ["sh", "-c", "echo ran"]
+        cwd = "/"
+        restart = "always"
+        "#,
+    );
+
+    let mut cmd = worker.start(&[&name]);
+    cmd.assert().success();
+
+    worker.wait_until("the project is restarted", || {
+        worker.log(&name).matches("ran").count() >= 2
+    });
+
+    let mut cmd = worker.status();
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains(&name));
+
+    let mut cmd = worker.stop(&[&name]);
+    cmd.assert().success();
+
+    let mut cmd = worker.status();
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains(&name).not());
+}
+
+#[test]
+fn test_restart_policy_never_is_default() {
+    let uuid = Uuid::new_v4();
+    let name = uuid.to_string();
+    let worker = WorkerTestConfig::with_project(
+        &uuid,
+        r#"
+        command = ["sh", "-c", "echo ran; exit 1"]
+        cwd = "/"
+        "#,
+    );
+
+    let mut cmd = worker.start(&[&name]);
+    cmd.assert().success();
+
+    // The supervisor stays around while it waits to restart the project, so once it has exited
+    // the project won't run again
+    worker.wait_for_log(&name, "ran");
+    worker.wait_until("the project has exited", || !is_running(&worker, &name));
+
+    let log = worker.log(&name);
+    assert_eq!(log.matches("ran").count(), 1);
+    assert!(!log.contains("restarting"));
+}
@@ -0,0 +1,97 @@
+use common::WorkerTestConfig;
+use predicates::prelude::predicate;
+use uuid::Uuid;
+
+mod common;
+
+// Says when it has set up the traps, so the signals aren't sent before
+const TRAPPING: &str = r#"
+    command = ["sh", "-c", "trap 'echo got hup' HUP; trap 'echo got usr1' USR1; echo trapping; while true; do sleep 0.1; done"]
+"#;
+
+fn start(worker: &WorkerTestConfig, name: &Uuid) {
+    let mut cmd = worker.start(&[&name.to_string()]);
+    cmd.assert().success();
+    worker.wait_for_log(&name.to_string(), "trapping");
+}
+
+#[test]
+fn test_signal_project() {
+    let uuid = Uuid::new_v4();
+    let worker = WorkerTestConfig::with_project(&uuid, TRAPPING);
+    start(&worker, &uuid);
+
+    let mut cmd = worker.cmd("signal", &[&uuid.to_string(), "hup"]);
+    cmd.assert().success();
+
+    // The supervisor survives the signal, and the project keeps running
+    worker.wait_for_log(&uuid.to_string(), "got hup");
+    assert!(!worker.pids(&uuid.to_string()).unwrap().is_empty());
+
+    let mut cmd = worker.stop(&[&uuid.to_string()]);
+    cmd.assert().success();
+}
+
+#[test]
+fn test_signal_unknown() {
+    let uuid = Uuid::new_v4();
+    let worker = WorkerTestConfig::with_project(&uuid, TRAPPING);
+    start(&worker, &uuid);
+
+    let mut cmd = worker.cmd("signal", &[&uuid.to_string(), "SIGFOO"]);
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("Unknown signal SIGFOO"));
+
+    let mut cmd = worker.stop(&[&uuid.to_string()]);
+    cmd.assert().success();
+}
+
+#[test]
+fn test_signal_fault_is_rejected() {
+    let uuid = Uuid::new_v4();
+    let worker = WorkerTestConfig::with_project(&uuid, TRAPPING);
+    start(&worker, &uuid);
+
+    let mut cmd = worker.cmd("signal", &[&uuid.to_string(), "SEGV"]);
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("SIGSEGV can't be sent"));
+    assert!(!worker.pids(&uuid.to_string()).unwrap().is_empty());
+
+    let mut cmd = worker.stop(&[&uuid.to_string()]);
+    cmd.assert().success();
+}
+
+#[test]
+fn test_reload_sends_reload_signal() {
+    let uuid = Uuid::new_v4();
+    let worker =
+        WorkerTestConfig::with_project(&uuid, &format!("{TRAPPING}\nreload_signal = \"SIGUSR1\""));
+    start(&worker, &uuid);
+    let pids = worker.pids(&uuid.to_string()).unwrap();
+
+    let mut cmd = worker.cmd("reload", &[&uuid.to_string()]);
+    cmd.assert().success();
+
+    worker.wait_for_log(&uuid.to_string(), "got usr1");
+    assert_eq!(worker.pids(&uuid.to_string()).unwrap(), pids);
+
+    let mut cmd = worker.stop(&[&uuid.to_string()]);
+    cmd.assert().success();
+}
+
+#[test]
+fn test_reload_without_reload_signal() {
+    let uuid = Uuid::new_v4();
+    let worker = WorkerTestConfig::with_project(&uuid, TRAPPING);
+    start(&worker, &uuid);
+
+    let mut cmd = worker.cmd("reload", &[&uuid.to_string()]);
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("has no reload_signal"));
+
+    let mut cmd = worker.stop(&[&uuid.to_string()]);
+    cmd.assert().success();
+}
@@ -56,3 +56,51 @@ fn test_status_multiple_running() {
     assert!(stdout.contains(&project2_name));
     assert!(!stdout.contains(&project3_name));
 }
+
+#[test]
+fn test_status_format_json() {
+    let worker = WorkerTestConfig::new();
+    let project_name = worker.project_name(&WorkerTestProject::One);
+
+    let mut cmd = worker.start(&[&project_name]);
+    cmd.assert().success();
+
+    let mut cmd = worker.cmd("status", &["--format", "json"]);
+    cmd.assert().success();
+
+    let output = cmd.output().unwrap().stdout;
+    let json: serde_json::Value = serde_json::from_slice(&output).unwrap();
+    let projects = json.as_array().unwrap();
+
+    assert_eq!(projects.len(), 1);
+    assert_eq!(projects[0]["name"], project_name.as_str());
+    assert_eq!(projects[0]["cwd"], "/");
+    assert!(projects[0]["pid"].is_i64());
+    assert!(projects[0]["uptime"].is_u64());
+    assert!(projects[0]["started_at"].is_u64());
+    assert!(projects[0]["cpu"].is_f64());
+    assert!(projects[0]["memory"].as_u64().unwrap() > 0);
+    assert!(projects[0]["processes"].as_u64().unwrap() >= 1);
+}
+
+#[test]
+fn test_status_format_table() {
+    let worker = WorkerTestConfig::new();
+    let project_name = worker.project_name(&WorkerTestProject::One);
+
+    let mut cmd = worker.start(&[&project_name]);
+    cmd.assert().success();
+
+    let mut cmd = worker.cmd("status", &["--format", "table"]);
+    cmd.assert().success();
+
+    let output = cmd.output().unwrap().stdout;
+    let stdout = std::str::from_utf8(&output).unwrap();
+    let lines: Vec<_> = stdout.lines().collect();
+
+    assert_eq!(lines.len(), 2);
+    assert!(lines[0].starts_with("NAME"));
+    assert!(lines[0].contains("CPU"));
+    assert!(lines[0].contains("MEM"));
+    assert!(lines[1].starts_with(&project_name));
+}
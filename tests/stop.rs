@@ -1,3 +1,5 @@
+use std::time::{Duration, Instant};
+
 use common::{WorkerTestConfig, WorkerTestProject};
 use uuid::Uuid;
 
@@ -234,3 +236,69 @@ fn test_stop_command_success() {
 
     assert_eq!(Err(PidError::FileNotFound), worker.pids(&uuid.to_string()));
 }
+
+#[test]
+fn test_stop_escalates_to_kill_after_timeout() {
+    let uuid = Uuid::new_v4();
+    let worker = WorkerTestConfig::with_project(
+        &uuid,
+        r#"
+        command = ["sh", "-c", "trap '' INT; sleep 30"]
+        cwd = "/"
+        stop_timeout = 1
+        "#,
+    );
+
+    let mut cmd = worker.start(&[&uuid.to_string()]);
+    cmd.assert().success();
+
+    let start = Instant::now();
+    let mut cmd = worker.stop(&[&uuid.to_string()]);
+    cmd.assert()
+        .success()
+        .stderr(format!("{uuid} did not stop in time, killing it\n"));
+
+    assert!(start.elapsed() >= Duration::from_secs(1));
+    assert_eq!(Err(PidError::FileNotFound), worker.pids(&uuid.to_string()));
+}
+
+#[test]
+fn test_stop_force_kills_immediately() {
+    let uuid = Uuid::new_v4();
+    let worker = WorkerTestConfig::with_project(
+        &uuid,
+        r#"
+        command = ["sh", "-c", "trap '' INT; sleep 30"]
+        cwd = "/"
+        stop_timeout = 30
+        "#,
+    );
+
+    let mut cmd = worker.start(&[&uuid.to_string()]);
+    cmd.assert().success();
+
+    let start = Instant::now();
+    let mut cmd = worker.stop(&["--force", &uuid.to_string()]);
+    cmd.assert().success().stderr("");
+
+    assert!(start.elapsed() < Duration::from_secs(5));
+    assert_eq!(Err(PidError::FileNotFound), worker.pids(&uuid.to_string()));
+}
+
+#[test]
+fn test_stop_cascade_stops_dependents() {
+    let worker = WorkerTestConfig::new();
+    let project_name = worker.project_name(&WorkerTestProject::Five);
+    let dep1_name = worker.project_name(&WorkerTestProject::One);
+    let dep2_name = worker.project_name(&WorkerTestProject::Two);
+
+    let mut cmd = worker.start(&[&project_name]);
+    cmd.assert().success();
+
+    let mut cmd = worker.stop(&["--cascade", &dep1_name]);
+    cmd.assert().success();
+
+    assert_eq!(Err(PidError::FileNotFound), worker.pids(&project_name));
+    assert_eq!(Err(PidError::FileNotFound), worker.pids(&dep1_name));
+    assert_eq!(worker.pids(&dep2_name).unwrap().len(), 1);
+}
@@ -0,0 +1,14 @@
+use common::WorkerTestConfig;
+use predicates::prelude::predicate;
+
+mod common;
+
+#[test]
+fn test_ui_needs_terminal() {
+    let worker = WorkerTestConfig::new();
+
+    let mut cmd = worker.cmd("ui", &[]);
+    cmd.assert().failure().stderr(predicate::str::contains(
+        "worker ui needs to run in a terminal",
+    ));
+}
@@ -0,0 +1,110 @@
+use std::time::Duration;
+
+use common::WorkerTestConfig;
+use tempfile::TempDir;
+use uuid::Uuid;
+
+mod common;
+
+fn started(worker: &WorkerTestConfig, name: &Uuid) -> usize {
+    let log_dir = worker.path().join(".worker/log");
+    [name.to_string(), format!("{name}.1")]
+        .iter()
+        .map(|file| {
+            std::fs::read_to_string(log_dir.join(file))
+                .unwrap_or_default()
+                .matches("started")
+                .count()
+        })
+        .sum()
+}
+
+#[test]
+fn test_watch_restarts_on_change() {
+    let uuid = Uuid::new_v4();
+    let cwd = TempDir::new().unwrap();
+    let worker = WorkerTestConfig::with_project(
+        &uuid,
+        &format!(
+            r#"
+            command = ["sh", "-c", "echo started; sleep 30"]
+            cwd = "{}"
+            watch = {{ debounce = 100 }}
+            "#,
+            cwd.path().display()
+        ),
+    );
+
+    let mut cmd = worker.start(&[&uuid.to_string()]);
+    cmd.assert().success();
+
+    // The files are watched before the command starts
+    worker.wait_for_log(&uuid.to_string(), "started");
+
+    std::fs::write(cwd.path().join("main.rs"), "fn main() {}").unwrap();
+    worker.wait_until("the project is restarted", || started(&worker, &uuid) == 2);
+    assert!(worker
+        .log(&format!("{uuid}.1"))
+        .contains("worker: files changed, restarting"));
+
+    let mut cmd = worker.stop(&[&uuid.to_string()]);
+    cmd.assert().success();
+}
+
+#[test]
+fn test_watch_ignores_excluded_files() {
+    let uuid = Uuid::new_v4();
+    let cwd = TempDir::new().unwrap();
+    let worker = WorkerTestConfig::with_project(
+        &uuid,
+        &format!(
+            r#"
+            command = ["sh", "-c", "echo started; sleep 30"]
+            cwd = "{}"
+            watch = {{ include = ["**/*.rs"], exclude = ["target/**"], debounce = 100 }}
+            "#,
+            cwd.path().display()
+        ),
+    );
+
+    let mut cmd = worker.start(&[&uuid.to_string()]);
+    cmd.assert().success();
+
+    // The files are watched before the command starts
+    worker.wait_for_log(&uuid.to_string(), "started");
+
+    std::fs::create_dir(cwd.path().join("target")).unwrap();
+    std::fs::write(cwd.path().join("target/main.rs"), "").unwrap();
+    std::fs::write(cwd.path().join("README.md"), "").unwrap();
+    // Nothing to poll for when the project isn't restarted, so wait a few debounce periods
+    std::thread::sleep(Duration::from_millis(1000));
+
+    assert_eq!(started(&worker, &uuid), 1);
+
+    let mut cmd = worker.stop(&[&uuid.to_string()]);
+    cmd.assert().success();
+}
+
+#[test]
+fn test_watch_restarts_while_project_writes_output() {
+    let uuid = Uuid::new_v4();
+    // Without a `cwd` the config dir is watched, which also holds the log of the project
+    let worker = WorkerTestConfig::with_project(
+        &uuid,
+        r#"
+        command = ["sh", "-c", "echo started; while true; do echo tick; sleep 0.1; done"]
+        watch = { include = ["*.txt"], debounce = 300 }
+        "#,
+    );
+
+    let mut cmd = worker.start(&[&uuid.to_string()]);
+    cmd.assert().success();
+
+    worker.wait_for_log(&uuid.to_string(), "tick");
+
+    std::fs::write(worker.path().join("a.txt"), "").unwrap();
+    worker.wait_until("the project is restarted", || started(&worker, &uuid) == 2);
+
+    let mut cmd = worker.stop(&[&uuid.to_string()]);
+    cmd.assert().success();
+}